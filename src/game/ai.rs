@@ -0,0 +1,432 @@
+//! This implements the CPU opponent. An `AiController` looks at the `Game` and fills in the same
+//! `PlayerInput` a human would produce with their keys, so it has to play by the same rules.
+//! How quickly it reacts, how often it catches and how well it aims depends on its `Difficulty`.
+
+use macroquad::prelude::*;
+use macroquad::rand::gen_range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::ball::BallState;
+use crate::game::input::PlayerInput;
+use crate::game::{other_team, Game};
+use crate::{FacingTo, PLAYER_HEIGHT, PLAYER_WIDTH};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// The time, in seconds, between each decision the AI makes
+    pub fn reaction_time(&self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.45,
+            Difficulty::Normal => 0.28,
+            Difficulty::Hard => 0.12,
+        }
+    }
+
+    /// The chance that the AI tries to catch an incoming ball, instead of dodging it
+    pub fn catch_chance(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.2,
+            Difficulty::Normal => 0.45,
+            Difficulty::Hard => 0.75,
+        }
+    }
+
+    /// The largest angle, in radians, that a throw can miss its target by
+    pub fn aim_error(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.35,
+            Difficulty::Normal => 0.18,
+            Difficulty::Hard => 0.06,
+        }
+    }
+
+    /// The chance that the AI takes a run-up for a super shot, when it has the ball
+    pub fn super_shot_chance(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.05,
+            Difficulty::Normal => 0.2,
+            Difficulty::Hard => 0.4,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+}
+
+/// What the AI intends to do with the ball it is holding. This is decided once per possession.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Plan {
+    Throw { target: usize, is_super_shot: bool },
+    Pass { target: usize },
+}
+
+pub struct AiController {
+    pub difficulty: Difficulty,
+    next_decision_time: f64,
+    last_input: PlayerInput,
+    plan: Option<Plan>,
+    plan_time: f64,
+    /// Rolled once for every incoming throw, so that the catch chance is not rolled every decision
+    will_catch: Option<bool>,
+    was_holding_ball: bool,
+}
+
+impl AiController {
+    /// The chance that an infielder passes to an outfielder, instead of throwing
+    const PASS_CHANCE: f32 = 0.25;
+    /// The AI will give up on a run-up and just throw, after this many seconds
+    const RUN_UP_TIMEOUT: f64 = 1.5;
+    /// The distance at which an incoming ball is caught or dodged
+    const DEFEND_DISTANCE: f32 = 140.;
+    /// Balls passing further away from the player than this are ignored
+    const THREAT_WIDTH: f32 = 48.;
+    /// The distance players keep to whoever is holding the ball
+    const CARRIER_SPACING: f32 = 120.;
+    /// The distance at which the AI stops moving towards where it wants to be
+    const ARRIVE_DISTANCE: f32 = 6.;
+
+    pub fn new(difficulty: Difficulty) -> Self {
+        AiController {
+            difficulty,
+            next_decision_time: 0.,
+            last_input: PlayerInput::default(),
+            plan: None,
+            plan_time: 0.,
+            will_catch: None,
+            was_holding_ball: false,
+        }
+    }
+
+    /// Returns the input for the player at `player_index` for this frame. A new decision is only
+    /// made once the reaction time has passed, and the last one is repeated until then.
+    pub fn think(&mut self, game: &Game, player_index: usize) -> PlayerInput {
+        let is_holding_ball = game.ball_held_by(player_index).is_some();
+        if is_holding_ball != self.was_holding_ball {
+            // getting or losing the ball should not carry over what we were doing before
+            self.was_holding_ball = is_holding_ball;
+            self.plan = None;
+            self.last_input = PlayerInput::default();
+            self.next_decision_time = game.time_passed + self.difficulty.reaction_time();
+        }
+        if !game.balls.iter().any(|ball| ball.is_live()) {
+            self.will_catch = None;
+        }
+        if game.time_passed < self.next_decision_time {
+            return self.last_input;
+        }
+        self.next_decision_time = game.time_passed + self.difficulty.reaction_time();
+        self.last_input = self.decide(game, player_index);
+        self.last_input
+    }
+
+    /// Attacking with a ball in hand comes first, then getting out of the way of the other
+    /// team's throws, and then going after a loose ball
+    fn decide(&mut self, game: &Game, player_index: usize) -> PlayerInput {
+        if game.ball_held_by(player_index).is_some() {
+            self.attack(game, player_index)
+        } else if let Some(ball_index) = incoming_ball(game, player_index) {
+            self.defend(game, player_index, ball_index)
+        } else if let Some(ball_index) = nearest_loose_ball(game, player_index) {
+            chase_ball(game, player_index, ball_index)
+        } else {
+            position(game, player_index)
+        }
+    }
+
+    fn attack(&mut self, game: &Game, player_index: usize) -> PlayerInput {
+        let plan = match self.plan {
+            Some(plan) => plan,
+            None => match self.make_plan(game, player_index) {
+                Some(plan) => {
+                    self.plan = Some(plan);
+                    self.plan_time = game.time_passed;
+                    plan
+                }
+                None => return PlayerInput::default(),
+            },
+        };
+        let player = &game.players[player_index];
+        let mut input = PlayerInput::default();
+        match plan {
+            Plan::Pass { target } => {
+                input.a = true;
+                input.target = Some(target);
+            }
+            Plan::Throw { target, is_super_shot } => {
+                let is_ready = player.state == crate::PlayerState::Running
+                    || game.time_passed - self.plan_time > Self::RUN_UP_TIMEOUT;
+                if is_super_shot && !is_ready {
+                    // run towards the other team, to get the run-up for a super shot
+                    let direction = game.players[target].pos.x - player.pos.x;
+                    input.right = direction > 0.;
+                    input.left = direction <= 0.;
+                } else {
+                    let aim_error = self.difficulty.aim_error();
+                    input.b = true;
+                    input.target = Some(target);
+                    input.aim_offset = gen_range(-aim_error, aim_error);
+                }
+            }
+        }
+        input
+    }
+
+    fn make_plan(&self, game: &Game, player_index: usize) -> Option<Plan> {
+        let team = game.team_of(player_index);
+        let player = &game.players[player_index];
+        let outfielder = game
+            .team_range(team)
+            .find(|&i| i != player_index && game.players[i].is_outfielder && game.players[i].life > 0);
+        if let Some(outfielder) = outfielder {
+            if !player.is_outfielder && gen_range(0., 1.) < Self::PASS_CHANCE {
+                return Some(Plan::Pass { target: outfielder });
+            }
+        }
+        let target = pick_throw_target(game, player_index)?;
+        let is_super_shot = gen_range(0., 1.) < self.difficulty.super_shot_chance();
+        Some(Plan::Throw { target, is_super_shot })
+    }
+
+    fn defend(&mut self, game: &Game, player_index: usize, ball_index: usize) -> PlayerInput {
+        let ball = &game.balls[ball_index];
+        let ball_facing_to = match ball.state {
+            BallState::OnAir(facing_to) => facing_to,
+            _ => return position(game, player_index),
+        };
+        let player = &game.players[player_index];
+        let center = player.pos + vec2(PLAYER_WIDTH, PLAYER_HEIGHT) / 2.;
+
+        let catch_chance = self.difficulty.catch_chance();
+        let will_catch = *self.will_catch.get_or_insert_with(|| gen_range(0., 1.) < catch_chance);
+        let mut input = PlayerInput::default();
+        if will_catch {
+            // a catch requires facing the thrower and holding the direction of the ball
+            let (facing_to, _) = FacingTo::opposite_direction(ball_facing_to);
+            let (expected_ball_facing_to, player_action) = FacingTo::opposite_direction(player.facing_to);
+            if expected_ball_facing_to == ball_facing_to {
+                input.b = true;
+                input.press(&player_action);
+            } else {
+                match facing_to {
+                    FacingTo::FacingLeft => input.left = true,
+                    FacingTo::FacingRight => input.right = true,
+                    FacingTo::FacingTop => input.up = true,
+                    _ => input.down = true,
+                }
+            }
+        } else if center.distance(ball.pos) < Self::DEFEND_DISTANCE / 2. && gen_range(0, 2) == 0 {
+            input.a = true;
+        } else {
+            input = evade(game, player_index, ball_index);
+        }
+        input
+    }
+}
+
+/// Returns the nearest ball that the player is threatened by, if any
+pub(crate) fn incoming_ball(game: &Game, player_index: usize) -> Option<usize> {
+    let position = game.players[player_index].pos;
+    (0..game.balls.len())
+        .filter(|&ball_index| is_threatened(game, player_index, ball_index))
+        .min_by(|&a, &b| {
+            let distance_a = game.balls[a].pos.distance(position);
+            let distance_b = game.balls[b].pos.distance(position);
+            distance_a.partial_cmp(&distance_b).unwrap()
+        })
+}
+
+/// A player is threatened by a live ball from the other team, that is close and heading its way
+pub(crate) fn is_threatened(game: &Game, player_index: usize, ball_index: usize) -> bool {
+    let ball = &game.balls[ball_index];
+    let thrower_team = ball.thrown_by.map(|thrower| game.team_of(thrower));
+    if !ball.is_live() || thrower_team == Some(game.team_of(player_index)) {
+        return false;
+    }
+    let player = &game.players[player_index];
+    let center = player.pos + vec2(PLAYER_WIDTH, PLAYER_HEIGHT) / 2.;
+    let to_player = center - ball.pos;
+    let is_incoming = ball.vel.dot(to_player) > 0.;
+    let miss_distance = ball.vel.normalize_or_zero().perp_dot(to_player).abs();
+    is_incoming
+        && miss_distance < AiController::THREAT_WIDTH
+        && to_player.length() < AiController::DEFEND_DISTANCE
+}
+
+/// Steps out of the path of the ball
+pub(crate) fn evade(game: &Game, player_index: usize, ball_index: usize) -> PlayerInput {
+    let player = &game.players[player_index];
+    let center = player.pos + vec2(PLAYER_WIDTH, PLAYER_HEIGHT) / 2.;
+    if game.balls[ball_index].pos.y < center.y {
+        PlayerInput { down: true, ..Default::default() }
+    } else {
+        PlayerInput { up: true, ..Default::default() }
+    }
+}
+
+/// Drives the players that are not the active player of their team, on both human and AI teams.
+/// They get out of the way of throws, send one player to pick up a loose ball and otherwise keep
+/// to their spot on the court. With several balls in play, a supporter may end up holding one,
+/// which it throws right away.
+pub(crate) fn support(game: &Game, player_index: usize) -> PlayerInput {
+    if let Some(ball_index) = incoming_ball(game, player_index) {
+        return evade(game, player_index, ball_index);
+    }
+    if game.ball_held_by(player_index).is_some() {
+        if let Some(target) = pick_throw_target(game, player_index) {
+            return PlayerInput { b: true, target: Some(target), ..Default::default() };
+        }
+    }
+    if let Some(ball_index) = nearest_loose_ball(game, player_index) {
+        if is_nearest_supporter(game, player_index, ball_index) {
+            return chase_ball(game, player_index, ball_index);
+        }
+    }
+    position(game, player_index)
+}
+
+/// Only the teammate that is nearest to the ball, not counting the active player, helps out
+fn is_nearest_supporter(game: &Game, player_index: usize, ball_index: usize) -> bool {
+    let team = game.team_of(player_index);
+    let active_player = game.get_active_player_for_team(team);
+    let ball_position = game.balls[ball_index].pos;
+    let nearest = game
+        .team_range(team)
+        .filter(|&i| Some(i) != active_player && game.players[i].life > 0)
+        .min_by(|&a, &b| {
+            let distance_a = game.players[a].pos.distance(ball_position);
+            let distance_b = game.players[b].pos.distance(ball_position);
+            distance_a.partial_cmp(&distance_b).unwrap()
+        });
+    nearest == Some(player_index)
+}
+
+/// Opponents with the least life left are targeted first, and the nearest of those is picked
+pub(crate) fn pick_throw_target(game: &Game, player_index: usize) -> Option<usize> {
+    let position = game.players[player_index].pos;
+    let other_team = other_team(game.team_of(player_index));
+    game.team_range(other_team)
+        .filter(|&i| !game.players[i].is_outfielder && game.players[i].life > 0)
+        .min_by(|&a, &b| {
+            let a = &game.players[a];
+            let b = &game.players[b];
+            a.life.cmp(&b.life).then_with(|| {
+                let distance_a = a.pos.distance(position);
+                let distance_b = b.pos.distance(position);
+                distance_a.partial_cmp(&distance_b).unwrap()
+            })
+        })
+}
+
+/// A loose ball can be picked up by a player if it is inside the area the player can move in
+pub(crate) fn can_reach_ball(game: &Game, player_index: usize, ball_index: usize) -> bool {
+    let player = &game.players[player_index];
+    let zone = game.field.player_zone(game.team_of(player_index), player.is_outfielder);
+    let reach = Rect::new(zone.x, zone.y, zone.w + PLAYER_WIDTH, zone.h + PLAYER_HEIGHT);
+    reach.contains(game.balls[ball_index].pos)
+}
+
+/// Returns the nearest loose ball that the player can reach, if any
+pub(crate) fn nearest_loose_ball(game: &Game, player_index: usize) -> Option<usize> {
+    let position = game.players[player_index].pos;
+    (0..game.balls.len())
+        .filter(|&ball_index| game.balls[ball_index].is_loose() && can_reach_ball(game, player_index, ball_index))
+        .min_by(|&a, &b| {
+            let distance_a = game.balls[a].pos.distance(position);
+            let distance_b = game.balls[b].pos.distance(position);
+            distance_a.partial_cmp(&distance_b).unwrap()
+        })
+}
+
+pub(crate) fn chase_ball(game: &Game, player_index: usize, ball_index: usize) -> PlayerInput {
+    let player = &game.players[player_index];
+    let ball = &game.balls[ball_index];
+    // the ball is picked up at the players hand, so we aim to stand next to it
+    let destination = ball.pos - vec2(PLAYER_WIDTH / 2., PLAYER_HEIGHT / 2.);
+    let mut input = move_towards(player.pos, destination);
+    if ball.state == BallState::OnGround && player.pos.distance(destination) < PLAYER_WIDTH / 2. {
+        input = PlayerInput { b: true, ..Default::default() };
+    }
+    input
+}
+
+/// Moves the player to its spot on the court, given the current situation, while keeping some
+/// distance to the nearest player that is holding a ball
+pub(crate) fn position(game: &Game, player_index: usize) -> PlayerInput {
+    let player = &game.players[player_index];
+    let mut destination = home_position(game, player_index);
+    let nearest_holder = game.balls.iter()
+        .filter_map(|ball| match ball.state {
+            BallState::OnPlayersHand(holder) => Some(holder),
+            _ => None,
+        })
+        .min_by(|&a, &b| {
+            let distance_a = game.players[a].pos.distance(destination);
+            let distance_b = game.players[b].pos.distance(destination);
+            distance_a.partial_cmp(&distance_b).unwrap()
+        });
+    if let Some(holder) = nearest_holder {
+        let holder_position = game.players[holder].pos;
+        let away = destination - holder_position;
+        if holder != player_index && away.length() < AiController::CARRIER_SPACING {
+            let direction = if away.length() > 0. { away.normalize() } else { vec2(0., 1.) };
+            destination = holder_position + direction * AiController::CARRIER_SPACING;
+        }
+    }
+    move_towards(player.pos, destination)
+}
+
+/// Every player has a lane, based on its place in the roster, to keep the team spread out.
+/// When the other team has the ball, infielders back off, to get more time to react to a throw.
+pub(crate) fn home_position(game: &Game, player_index: usize) -> Vec2 {
+    let team = game.team_of(player_index);
+    let player = &game.players[player_index];
+    let zone = game.field.player_zone(team, player.is_outfielder);
+    let team_range = game.team_range(team);
+    let roster_size = team_range.len() as f32;
+    let slot = (player_index - team_range.start) as f32;
+    let lane = zone.y + zone.h * (slot + 1.) / (roster_size + 1.);
+    if player.is_outfielder {
+        // stay in line with the nearest ball, to be ready for a pass
+        let nearest_ball = game.balls.iter().min_by(|a, b| {
+            let distance_a = a.pos.distance(player.pos);
+            let distance_b = b.pos.distance(player.pos);
+            distance_a.partial_cmp(&distance_b).unwrap()
+        });
+        let y = nearest_ball.map_or(lane, |ball| ball.pos.y);
+        return vec2(zone.x + zone.w / 2., y.clamp(zone.y, zone.y + zone.h));
+    }
+    let is_other_team_attacking = game.balls.iter().any(|ball| match ball.state {
+        BallState::OnPlayersHand(holder) => game.team_of(holder) != team,
+        _ => false,
+    });
+    let (front, back) = match team {
+        crate::Team::One => (zone.x + zone.w, zone.x),
+        crate::Team::Two => (zone.x, zone.x + zone.w),
+    };
+    let depth = if is_other_team_attacking { 0.8 } else { 0.4 };
+    vec2(front + (back - front) * depth, lane)
+}
+
+pub(crate) fn move_towards(from: Vec2, to: Vec2) -> PlayerInput {
+    let delta = to - from;
+    PlayerInput {
+        up: delta.y < -AiController::ARRIVE_DISTANCE,
+        right: delta.x > AiController::ARRIVE_DISTANCE,
+        down: delta.y > AiController::ARRIVE_DISTANCE,
+        left: delta.x < -AiController::ARRIVE_DISTANCE,
+        ..Default::default()
+    }
+}
@@ -100,14 +100,14 @@ impl AnimationPlayer {
                 )
             });
 
-        let texture = texture_resource.texture;
+        let frame_size = params.frame_size.or(texture_resource.meta.sprite_size);
+        Self::with_texture(params, texture_resource.texture, frame_size)
+    }
 
-        let frame_size = params.frame_size.unwrap_or_else(|| {
-            texture_resource
-                .meta
-                .sprite_size
-                .unwrap_or_else(|| vec2(texture.width(), texture.height()).as_u32())
-        });
+    /// Creates a player for a texture that is not in `Resources`. If no frame size is given, the
+    /// whole texture is a single frame
+    pub fn with_texture(params: AnimationParams, texture: Texture2D, frame_size: Option<UVec2>) -> Self {
+        let frame_size = frame_size.unwrap_or_else(|| vec2(texture.width(), texture.height()).as_u32());
 
         let tint = params.tint.unwrap_or(color::WHITE);
 
@@ -214,10 +214,19 @@ impl AnimationPlayer {
         self.sprite.frame().dest_size * self.scale
     }
 
+    /// The offset the frame is drawn at, relative to the position it is drawn at
+    pub fn get_offset(&self) -> Vec2 {
+        self.offset
+    }
+
     pub fn set_scale(&mut self, scale: f32) {
         self.scale = scale;
     }
 
+    pub fn set_tint(&mut self, tint: Color) {
+        self.tint = tint;
+    }
+
     pub fn get_animation(&self, id: &str) -> Option<&AnimationMetadata> {
         self.animations.iter().find(|a| a.id == id)
     }
@@ -0,0 +1,418 @@
+//! This implements sound effects and music. Sounds are declared in the `sounds.json` file, as
+//! `SoundParams`, and are played by their id, through `Audio`, which is kept in storage. Gameplay
+//! sounds are played by listening to the events of `Game`, and every court has a playlist of music,
+//! that moves on to the next track every round.
+//!
+//! `Audio` does not talk to the sound device itself, but to an `AudioBackend`. The game uses
+//! `MacroquadAudioBackend`, and `NullAudioBackend` plays nothing, so that the game can be run
+//! without an audio device, with the `--no-audio` argument.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use macroquad::audio::{load_sound, play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound};
+use macroquad::prelude::collections::storage;
+use macroquad::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::events::{GameEvent, GameEventListener};
+use crate::game::resources::Resources;
+use crate::game::settings::Settings;
+use crate::helpers::text::ToStringHelper;
+
+/// This is a declaration of a sound, with the files it is played from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoundParams {
+    /// The id of the sound. This is what gameplay code, and court playlists, use to play it
+    pub id: String,
+    /// The paths of the files, relative to the assets directory. A random one of them is played
+    /// every time, so that a sound that is heard a lot does not get repetitive
+    pub files: Vec<String>,
+    /// The volume the sound is played at, from 0 to 1, before the volume settings are applied
+    #[serde(default = "SoundParams::default_volume")]
+    pub volume: f32,
+    /// Every time the sound is played, its volume is changed by a random amount of up to this
+    #[serde(default)]
+    pub volume_variation: f32,
+}
+
+impl SoundParams {
+    const DEFAULT_VOLUME: f32 = 1.;
+
+    pub fn default_volume() -> f32 {
+        Self::DEFAULT_VOLUME
+    }
+}
+
+/// Plays sound files, by their path
+pub trait AudioBackend {
+    fn play(&mut self, file: &str, volume: f32, looped: bool);
+    fn stop(&mut self, file: &str);
+    fn set_volume(&mut self, file: &str, volume: f32);
+}
+
+/// A backend that plays nothing, for when there is no audio device
+pub struct NullAudioBackend;
+
+impl AudioBackend for NullAudioBackend {
+    fn play(&mut self, _file: &str, _volume: f32, _looped: bool) {}
+
+    fn stop(&mut self, _file: &str) {}
+
+    fn set_volume(&mut self, _file: &str, _volume: f32) {}
+}
+
+/// A backend that plays sounds through `macroquad::audio`. Files are loaded up front
+pub struct MacroquadAudioBackend {
+    sounds: HashMap<String, Sound>,
+}
+
+impl MacroquadAudioBackend {
+    /// Loads every file of `sounds`. Files that can not be loaded are left out, so that a missing
+    /// sound will not keep the game from starting
+    pub async fn load(assets_dir: &str, sounds: &[SoundParams]) -> Self {
+        let mut loaded = HashMap::new();
+        for file in sounds.iter().flat_map(|sound| &sound.files) {
+            if loaded.contains_key(file) {
+                continue;
+            }
+            let path = Path::new(assets_dir).join(file);
+            match load_sound(&path.to_string_helper()).await {
+                Ok(sound) => {
+                    loaded.insert(file.clone(), sound);
+                }
+                Err(err) => eprintln!("Could not load the sound '{}': {}", file, err),
+            }
+        }
+        MacroquadAudioBackend { sounds: loaded }
+    }
+}
+
+impl AudioBackend for MacroquadAudioBackend {
+    fn play(&mut self, file: &str, volume: f32, looped: bool) {
+        if let Some(&sound) = self.sounds.get(file) {
+            play_sound(sound, PlaySoundParams { looped, volume });
+        }
+    }
+
+    fn stop(&mut self, file: &str) {
+        if let Some(&sound) = self.sounds.get(file) {
+            stop_sound(sound);
+        }
+    }
+
+    fn set_volume(&mut self, file: &str, volume: f32) {
+        if let Some(&sound) = self.sounds.get(file) {
+            set_sound_volume(sound, volume);
+        }
+    }
+}
+
+pub struct Audio {
+    backend: Box<dyn AudioBackend>,
+    sounds: HashMap<String, SoundParams>,
+    /// The playlist of the court that is being played on
+    playlist: Vec<String>,
+    /// The index of the track in `playlist` that is playing
+    track: usize,
+    /// The file of the music that is playing, and the volume of its sound
+    music: Option<(String, f32)>,
+    /// The settings that the volume of sounds is taken from
+    settings: Settings,
+}
+
+impl Audio {
+    pub const THROW_SOUND_ID: &'static str = "throw";
+    pub const SUPER_THROW_SOUND_ID: &'static str = "super_throw";
+    pub const HIT_SOUND_ID: &'static str = "hit";
+    pub const CATCH_SOUND_ID: &'static str = "catch";
+    pub const BOUNCE_SOUND_ID: &'static str = "bounce";
+    pub const WHISTLE_SOUND_ID: &'static str = "whistle";
+    pub const CROWD_CHEER_SOUND_ID: &'static str = "crowd_cheer";
+
+    pub fn new(backend: Box<dyn AudioBackend>, sounds: &[SoundParams], settings: &Settings) -> Self {
+        Audio {
+            backend,
+            sounds: sounds.iter().map(|sound| (sound.id.clone(), sound.clone())).collect(),
+            playlist: Vec::new(),
+            track: 0,
+            music: None,
+            settings: settings.clone(),
+        }
+    }
+
+    /// Plays the sound effect with the given id. Unknown ids are ignored, like missing particle
+    /// effects are
+    pub fn play(&mut self, id: &str) {
+        if let Some((file, volume)) = self.pick_file(id) {
+            let effects_volume = self.settings.master_volume * self.settings.effects_volume;
+            self.backend.play(&file, volume * effects_volume, false);
+        }
+    }
+
+    /// Starts a round, with a whistle, and the next track of the court's playlist. The playlist
+    /// starts over when the court changes
+    pub fn start_round(&mut self, playlist: &[String]) {
+        self.play(Self::WHISTLE_SOUND_ID);
+        if self.playlist != playlist {
+            self.playlist = playlist.to_vec();
+            self.track = 0;
+        } else if !self.playlist.is_empty() {
+            self.track = (self.track + 1) % self.playlist.len();
+        }
+        self.stop_music();
+        if let Some(id) = self.playlist.get(self.track).cloned() {
+            if let Some((file, volume)) = self.pick_file(&id) {
+                self.backend.play(&file, volume * self.music_volume(), true);
+                self.music = Some((file, volume));
+            }
+        }
+    }
+
+    pub fn stop_music(&mut self) {
+        if let Some((file, _)) = self.music.take() {
+            self.backend.stop(&file);
+        }
+    }
+
+    /// Takes on changes to the volume settings, and applies them to the music that is playing
+    pub fn set_settings(&mut self, settings: &Settings) {
+        self.settings = settings.clone();
+        if let Some((file, volume)) = &self.music {
+            self.backend.set_volume(file, volume * self.music_volume());
+        }
+    }
+
+    fn music_volume(&self) -> f32 {
+        self.settings.master_volume * self.settings.music_volume
+    }
+
+    /// Picks a random file of a sound, and the volume to play it at, with the variation applied
+    fn pick_file(&self, id: &str) -> Option<(String, f32)> {
+        let sound = self.sounds.get(id)?;
+        if sound.files.is_empty() {
+            return None;
+        }
+        let file = sound.files[rand::gen_range(0, sound.files.len())].clone();
+        let variation = if sound.volume_variation > 0. {
+            rand::gen_range(-sound.volume_variation, sound.volume_variation)
+        } else {
+            0.
+        };
+        Some((file, (sound.volume + variation).clamp(0., 1.)))
+    }
+}
+
+impl GameEventListener for Audio {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::BallThrown { super_shot: true, .. } => self.play(Self::SUPER_THROW_SOUND_ID),
+            GameEvent::BallThrown { .. } | GameEvent::BallPassed { .. } => self.play(Self::THROW_SOUND_ID),
+            GameEvent::PlayerHit { .. } => self.play(Self::HIT_SOUND_ID),
+            GameEvent::BallCaught { .. } => self.play(Self::CATCH_SOUND_ID),
+            GameEvent::BallOutOfBounds { .. } => self.play(Self::BOUNCE_SOUND_ID),
+            GameEvent::PlayerEliminated { .. } => self.play(Self::CROWD_CHEER_SOUND_ID),
+            GameEvent::RoundWon { .. } => {
+                self.play(Self::WHISTLE_SOUND_ID);
+                self.play(Self::CROWD_CHEER_SOUND_ID);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Loads the sounds declared in `Resources`, and puts `Audio` in storage, with the `Settings` in
+/// storage, if there are any. If `enabled` is false, nothing is loaded, and `NullAudioBackend` is
+/// used
+pub async fn load_audio(enabled: bool) {
+    let (assets_dir, sounds) = {
+        let resources = storage::get::<Resources>();
+        (resources.assets_dir.clone(), resources.sounds.clone())
+    };
+    let backend: Box<dyn AudioBackend> = if enabled {
+        Box::new(MacroquadAudioBackend::load(&assets_dir, &sounds).await)
+    } else {
+        Box::new(NullAudioBackend)
+    };
+    let settings = storage::try_get::<Settings>().map(|settings| settings.clone()).unwrap_or_default();
+    storage::store(Audio::new(backend, &sounds, &settings));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::game::events::HitZone;
+    use crate::Team;
+
+    /// What was asked of a `RecordingAudioBackend`
+    #[derive(Debug, Clone, PartialEq)]
+    enum Call {
+        Play { file: String, volume: f32, looped: bool },
+        Stop { file: String },
+        SetVolume { file: String, volume: f32 },
+    }
+
+    /// A backend that records what it is asked to do, instead of playing anything
+    #[derive(Default)]
+    struct RecordingAudioBackend {
+        calls: Rc<RefCell<Vec<Call>>>,
+    }
+
+    impl AudioBackend for RecordingAudioBackend {
+        fn play(&mut self, file: &str, volume: f32, looped: bool) {
+            self.calls.borrow_mut().push(Call::Play { file: file.to_string(), volume, looped });
+        }
+
+        fn stop(&mut self, file: &str) {
+            self.calls.borrow_mut().push(Call::Stop { file: file.to_string() });
+        }
+
+        fn set_volume(&mut self, file: &str, volume: f32) {
+            self.calls.borrow_mut().push(Call::SetVolume { file: file.to_string(), volume });
+        }
+    }
+
+    /// A sound with a single file, which is named after the id
+    fn sound(id: &str, volume: f32) -> SoundParams {
+        SoundParams {
+            id: id.to_string(),
+            files: vec![format!("{}.ogg", id)],
+            volume,
+            volume_variation: 0.,
+        }
+    }
+
+    fn play(file: &str, volume: f32, looped: bool) -> Call {
+        Call::Play { file: file.to_string(), volume, looped }
+    }
+
+    /// `Audio` with every gameplay sound, and two music tracks, and the calls made to its backend
+    fn recording_audio(settings: &Settings) -> (Audio, Rc<RefCell<Vec<Call>>>) {
+        let backend = RecordingAudioBackend::default();
+        let calls = backend.calls.clone();
+        let mut sounds: Vec<SoundParams> = [
+            Audio::THROW_SOUND_ID,
+            Audio::SUPER_THROW_SOUND_ID,
+            Audio::HIT_SOUND_ID,
+            Audio::CATCH_SOUND_ID,
+            Audio::BOUNCE_SOUND_ID,
+            Audio::WHISTLE_SOUND_ID,
+            Audio::CROWD_CHEER_SOUND_ID,
+        ]
+        .iter()
+        .map(|id| sound(id, 1.))
+        .collect();
+        sounds.push(sound("track_a", 0.5));
+        sounds.push(sound("track_b", 0.5));
+        (Audio::new(Box::new(backend), &sounds, settings), calls)
+    }
+
+    fn full_volume() -> Settings {
+        Settings {
+            master_volume: 1.,
+            music_volume: 1.,
+            effects_volume: 1.,
+            ..Settings::default()
+        }
+    }
+
+    /// The files that were played, in order
+    fn played(calls: &RefCell<Vec<Call>>) -> Vec<String> {
+        calls.borrow().iter()
+            .filter_map(|call| match call {
+                Call::Play { file, .. } => Some(file.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_event_sounds() {
+        let (mut audio, calls) = recording_audio(&full_volume());
+        let events = [
+            GameEvent::BallThrown { ball: 0, thrower: 0, super_shot: false },
+            GameEvent::BallThrown { ball: 0, thrower: 0, super_shot: true },
+            GameEvent::BallPassed { ball: 0, passer: 0 },
+            GameEvent::PlayerHit {
+                ball: 0,
+                player: 1,
+                thrower: Some(0),
+                zone: HitZone::Body,
+                damage: 10,
+                pos: Vec2::ZERO,
+                velocity: Vec2::ZERO,
+                max_speed: 1.,
+            },
+            GameEvent::BallCaught { ball: 0, player: 1, from_opponent: true, pos: Vec2::ZERO },
+            GameEvent::BallOutOfBounds { ball: 0, pos: Vec2::ZERO },
+            GameEvent::PlayerEliminated { player: 1, by: Some(0) },
+            GameEvent::RoundWon { team: Team::One },
+            // these have no sound
+            GameEvent::BallPickedUp { ball: 0, player: 1 },
+            GameEvent::Footstep { player: 1, pos: Vec2::ZERO },
+        ];
+        for event in &events {
+            audio.on_event(event);
+        }
+        assert_eq!(played(&calls), [
+            "throw.ogg",
+            "super_throw.ogg",
+            "throw.ogg",
+            "hit.ogg",
+            "catch.ogg",
+            "bounce.ogg",
+            "crowd_cheer.ogg",
+            "whistle.ogg",
+            "crowd_cheer.ogg",
+        ]);
+    }
+
+    #[test]
+    fn test_start_round_playlist() {
+        let (mut audio, calls) = recording_audio(&full_volume());
+        let playlist = vec!["track_a".to_string(), "track_b".to_string()];
+        audio.start_round(&playlist);
+        audio.start_round(&playlist);
+        audio.start_round(&playlist);
+        assert_eq!(played(&calls), [
+            "whistle.ogg", "track_a.ogg",
+            "whistle.ogg", "track_b.ogg",
+            "whistle.ogg", "track_a.ogg",
+        ]);
+        // the music of the last round is stopped when the next one starts
+        assert!(calls.borrow().contains(&Call::Stop { file: "track_a.ogg".to_string() }));
+        assert!(calls.borrow().contains(&Call::Stop { file: "track_b.ogg".to_string() }));
+
+        // a court with another playlist starts it from the first track
+        calls.borrow_mut().clear();
+        let other_playlist = vec!["track_b".to_string(), "track_a".to_string()];
+        audio.start_round(&other_playlist);
+        assert_eq!(played(&calls), ["whistle.ogg", "track_b.ogg"]);
+    }
+
+    #[test]
+    fn test_volume_settings() {
+        let settings = Settings {
+            master_volume: 0.5,
+            music_volume: 0.5,
+            effects_volume: 0.8,
+            ..Settings::default()
+        };
+        let (mut audio, calls) = recording_audio(&settings);
+        audio.start_round(&["track_a".to_string()]);
+        assert_eq!(*calls.borrow(), [play("whistle.ogg", 0.4, false), play("track_a.ogg", 0.125, true)]);
+
+        // changing the settings changes the volume of the music that is playing
+        calls.borrow_mut().clear();
+        audio.set_settings(&full_volume());
+        audio.play(Audio::HIT_SOUND_ID);
+        assert_eq!(*calls.borrow(), [
+            Call::SetVolume { file: "track_a.ogg".to_string(), volume: 0.5 },
+            play("hit.ogg", 1., false),
+        ]);
+    }
+}
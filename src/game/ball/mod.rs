@@ -1,6 +1,8 @@
 use macroquad::color::Color;
+use crate::game::animations::AnimationParams;
 use crate::game::ball::animations::BallAnimationParams;
 use crate::{AnimationPlayer, FacingTo, Player, Vec2};
+use crate::math::reflect;
 use serde::{Deserialize, Serialize};
 
 pub mod animations;
@@ -8,6 +10,8 @@ pub mod animations;
 #[derive(Clone, Debug)]
 pub struct Ball {
     pub(crate) pos: Vec2,
+    /// The position at the start of the frame, which collisions are swept from
+    pub(crate) prev_pos: Vec2,
     pub(crate) vel: Vec2,
     pub(crate) r: f32,
     pub(crate) color: Color,
@@ -16,15 +20,24 @@ pub struct Ball {
     pub(crate) dropped: bool,
     pub(crate) in_air: bool,
     pub(crate) grabbed_by: Option<usize>,
+    pub(crate) thrown_by: Option<usize>,
     pub(crate) animation: usize,
     pub(crate) stopped: bool,
     pub(crate) state: BallState,
     pub(crate) tick: f64,
+    pub(crate) super_shot: bool,
+    /// The height at which a falling ball bounces back up
+    pub(crate) gravity_line: f32,
+    pub(crate) physics: BallPhysics,
+    /// The players that have ducked under this ball since it was thrown, so that a dodge is only
+    /// counted once
+    pub(crate) dodged_by: Vec<usize>,
 }
 
 impl Ball {
-    pub(crate) fn outside_edge(&mut self, time_passed: f64, left_or_right_edge: bool, top_or_bottom_edge: bool) {
-        self.state = BallState::AfterHittingBoundary { time_passed };
+    /// Bounces the ball off the edge of the field. `normal` points back into the field
+    pub(crate) fn outside_edge(&mut self, time_passed: f64, normal: Vec2) {
+        self.state = BallState::AfterHittingBoundary { normal, time_passed };
     }
 }
 
@@ -33,9 +46,12 @@ impl Ball {
     pub const IDLE_ANIMATION_ID: &'static str = "idle";
     pub const MOVE_ANIMATION_ID: &'static str = "move";
 
+    pub const SUPER_SHOT_SPEED_FACTOR: f32 = 1.6;
+
     pub(crate) fn default() -> Ball {
         Ball {
             pos: Default::default(),
+            prev_pos: Default::default(),
             vel: Default::default(),
             r: 0.0,
             color: Default::default(),
@@ -44,10 +60,15 @@ impl Ball {
             dropped: false,
             in_air: false,
             grabbed_by: Some(0),
+            thrown_by: None,
             animation: 0,
             stopped: true,
             state: BallState::OnPlayersHand(0),
             tick: 0.0,
+            super_shot: false,
+            gravity_line: 0.,
+            physics: BallPhysics::default(),
+            dodged_by: Vec::new(),
         }
     }
 
@@ -60,42 +81,76 @@ impl Ball {
         self.dropped = false;
         self.in_air = false;
         self.stopped = true;
+        self.super_shot = false;
         self.vel = Vec2::new(0., 0.);
         self.state = BallState::OnPlayersHand(player_index);
     }
 
+    /// The damage dealt to a player hit by this ball
+    pub(crate) fn damage(&self) -> i32 {
+        if self.super_shot {
+            self.physics.base_damage * 2
+        } else {
+            self.physics.base_damage
+        }
+    }
+
+    /// A ball is live while it is flying from a throw, and only a live ball can hurt a player
+    pub(crate) fn is_live(&self) -> bool {
+        matches!(self.state, BallState::OnAir(_))
+    }
+
+    pub(crate) fn is_held(&self) -> bool {
+        matches!(self.state, BallState::OnPlayersHand(_))
+    }
+
+    /// A loose ball is neither held nor live, and is up for grabs by either team
+    pub(crate) fn is_loose(&self) -> bool {
+        !self.is_held() && !self.is_live()
+    }
+
     #[inline]
     pub(crate) fn throwing(&mut self, target_pos: Vec2, thrower_position: Vec2, facing_to: FacingTo) {
         self.pos = thrower_position;
-        self.vel = target_pos * 5.;
+        self.vel = target_pos * self.physics.throw_speed;
         self.thrown = true;
         self.collided = false;
         self.in_air = true;
-        self.grabbed_by = None;
+        self.thrown_by = self.grabbed_by.take();
         self.stopped = false;
+        self.super_shot = false;
+        self.dodged_by.clear();
         self.state = BallState::OnAir(facing_to);
     }
 
+    #[inline]
+    pub(crate) fn super_throwing(&mut self, target_pos: Vec2, thrower_position: Vec2, facing_to: FacingTo) {
+        self.throwing(target_pos, thrower_position, facing_to);
+        self.vel *= Self::SUPER_SHOT_SPEED_FACTOR;
+        self.super_shot = true;
+    }
+
     #[inline]
     fn stopping(&mut self) {
         self.collided = false;
         self.stopped = true;
         self.in_air = false;
+        self.super_shot = false;
         self.vel = Vec2::default();
         self.grabbed_by = None;
         self.state = BallState::OnGround;
     }
 
-    pub(crate) fn ball_falling(&mut self, time_passed: f64, gravity: Vec2, gravity_line: f32) {
+    pub(crate) fn ball_falling(&mut self, time_passed: f64, gravity: Vec2) {
         // dt = t' - t
         // pos' = pos + dt * v
         // v' = v + dt * G
         let dt = (time_passed - self.tick) as f32;
         self.tick = time_passed;
-        self.vel -= self.vel / 5.;
+        self.vel -= self.vel * self.physics.friction;
         self.vel += gravity * dt;
-        if self.pos.y >= gravity_line {
-            self.vel.y = -self.vel.y;
+        if self.pos.y >= self.gravity_line {
+            self.vel.y = -self.vel.y * self.physics.bounciness;
         }
         self.pos += self.vel;
         if self.vel.length() < 0.1 {
@@ -103,28 +158,36 @@ impl Ball {
         }
     }
 
-    pub(crate) fn after_collision(&mut self, change_x: bool, change_y: bool, time_passed: f64) {
+    /// Reflects the ball off whatever it hit, along the contact `normal`, which is zero when the
+    /// velocity was already changed by the collision
+    pub(crate) fn after_collision(&mut self, normal: Vec2, time_passed: f64) {
         self.collided = true;
         self.dropped = true;
+        self.super_shot = false;
         self.tick = time_passed;
-        if change_y {
-            self.vel.y *= -1.;
-        }
-        if change_x {
-            self.vel.x *= -1.;
-        }
+        self.vel = reflect(self.vel, normal, self.physics.bounciness);
         self.state = BallState::BallFalling {time_passed}
     }
 
+    /// The fastest this ball can fly, which is higher for a super shot
+    pub(crate) fn max_speed(&self) -> f32 {
+        if self.super_shot {
+            self.physics.max_speed * Self::SUPER_SHOT_SPEED_FACTOR
+        } else {
+            self.physics.max_speed
+        }
+    }
+
     pub(crate) fn move_ball(&mut self) {
-        if self.vel.length() > 5. {
-            self.vel = self.vel.normalize() * 5.;
+        let max_speed = self.max_speed();
+        if self.vel.length() > max_speed {
+            self.vel = self.vel.normalize() * max_speed;
         }
         self.pos += self.vel;
     }
 
     pub(crate) fn stop(&mut self) {
-        self.vel -= self.vel / 5.;
+        self.vel -= self.vel * self.physics.friction;
         if self.vel.length() < 0.1 {
             self.stopping();
         }
@@ -152,6 +215,98 @@ pub struct BallParams {
     /// `PlayerCharacterParams` entry.
     #[serde(flatten)]
     pub animation: BallAnimationParams,
+    /// This holds the physical properties of the ball. This is flattened, like `animation`.
+    #[serde(flatten)]
+    pub physics: BallPhysics,
+}
+
+impl BallParams {
+    /// Makes the animation player of a ball, playing the idle animation. This panics if the
+    /// texture of the ball is not in `Resources`
+    pub fn animation_player(&self) -> AnimationPlayer {
+        let params: AnimationParams = self.animation.clone().into();
+        let mut animation_player = AnimationPlayer::new(params);
+        animation_player.set_animation(Ball::IDLE_ANIMATION_ID);
+        animation_player
+    }
+}
+
+/// The physical properties of a ball, which decide how it plays
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct BallPhysics {
+    /// This decides how much a ball is pushed around when it collides with another ball
+    #[serde(default = "BallPhysics::default_mass")]
+    pub mass: f32,
+    /// The radius used for collisions
+    #[serde(default = "BallPhysics::default_radius")]
+    pub radius: f32,
+    /// The speed of the ball when it leaves the hand of the thrower
+    #[serde(default = "BallPhysics::default_throw_speed")]
+    pub throw_speed: f32,
+    /// The fastest the ball can fly, outside of super shots
+    #[serde(default = "BallPhysics::default_max_speed")]
+    pub max_speed: f32,
+    /// The part of the speed that is kept when bouncing. 1 is a perfect bounce
+    #[serde(default = "BallPhysics::default_bounciness")]
+    pub bounciness: f32,
+    /// The part of the speed that is lost every frame, while the ball is rolling or falling
+    #[serde(default = "BallPhysics::default_friction")]
+    pub friction: f32,
+    /// The damage dealt to a player that is hit. This is doubled for a super shot
+    #[serde(default = "BallPhysics::default_base_damage")]
+    pub base_damage: i32,
+}
+
+impl BallPhysics {
+    const DEFAULT_MASS: f32 = 1.;
+    const DEFAULT_RADIUS: f32 = 16.;
+    const DEFAULT_THROW_SPEED: f32 = 5.;
+    const DEFAULT_MAX_SPEED: f32 = 5.;
+    const DEFAULT_BOUNCINESS: f32 = 1.;
+    const DEFAULT_FRICTION: f32 = 0.2;
+    const DEFAULT_BASE_DAMAGE: i32 = 10;
+
+    pub fn default_mass() -> f32 {
+        Self::DEFAULT_MASS
+    }
+
+    pub fn default_radius() -> f32 {
+        Self::DEFAULT_RADIUS
+    }
+
+    pub fn default_throw_speed() -> f32 {
+        Self::DEFAULT_THROW_SPEED
+    }
+
+    pub fn default_max_speed() -> f32 {
+        Self::DEFAULT_MAX_SPEED
+    }
+
+    pub fn default_bounciness() -> f32 {
+        Self::DEFAULT_BOUNCINESS
+    }
+
+    pub fn default_friction() -> f32 {
+        Self::DEFAULT_FRICTION
+    }
+
+    pub fn default_base_damage() -> i32 {
+        Self::DEFAULT_BASE_DAMAGE
+    }
+}
+
+impl Default for BallPhysics {
+    fn default() -> Self {
+        BallPhysics {
+            mass: Self::default_mass(),
+            radius: Self::default_radius(),
+            throw_speed: Self::default_throw_speed(),
+            max_speed: Self::default_max_speed(),
+            bounciness: Self::default_bounciness(),
+            friction: Self::default_friction(),
+            base_damage: Self::default_base_damage(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -159,8 +314,11 @@ pub enum BallState {
     OnGround,
     OnAir(FacingTo),
     OnPlayersHand(usize),
-    AfterHittingPlayer { change_x: bool, change_y: bool, time_passed: f64 },
-    AfterHittingBoundary { time_passed: f64 },
+    /// `normal` points from the player to the ball
+    AfterHittingPlayer { normal: Vec2, time_passed: f64 },
+    /// `normal` points back into the field
+    AfterHittingBoundary { normal: Vec2, time_passed: f64 },
+    AfterHittingBall { time_passed: f64 },
     BallFalling { time_passed: f64 },
     Stopping,
 }
\ No newline at end of file
@@ -1,10 +1,9 @@
 use macroquad::{
-    experimental::scene::{self, RefMut},
     prelude::*,
     rand::gen_range,
 };
 
-use crate::{noise::NoiseGenerator, Player};
+use crate::noise::NoiseGenerator;
 
 struct Shake {
     direction: (f32, f32),
@@ -29,6 +28,7 @@ pub struct GameCamera {
     shake: Vec<Shake>,
     noisegen: NoiseGenerator,
     noisegen_position: f32,
+    camera: Camera2D,
 
     pub manual: Option<(Vec2, f32)>,
 }
@@ -44,6 +44,7 @@ impl GameCamera {
             manual: None,
             noisegen: NoiseGenerator::new(5),
             noisegen_position: 5.0,
+            camera: Camera2D::from_display_rect(bounds),
         }
     }
 }
@@ -162,27 +163,31 @@ impl GameCamera {
     }
 }
 
-impl scene::Node for GameCamera {
-    fn fixed_update(mut node: RefMut<Self>) {
+impl GameCamera {
+    /// Frames every rect in `targets`, smoothing the result over the follow buffer. `zoom_scale`
+    /// is applied on top of the computed framing, so values above 1 will zoom in.
+    pub fn update(&mut self, targets: &[Rect], zoom_scale: f32) {
         {
-            let players = scene::find_nodes_by_type::<Player>();
             let aspect = screen_width() / screen_height();
 
-            let mut players_amount = 0;
+            let mut targets_amount = 0;
             let mut middle_point = vec2(0., 0.);
             let mut min = vec2(10000., 10000.);
             let mut max = vec2(-10000., -10000.);
 
-            for player in players {
-                let camera_pox_middle = player.camera_box.point() + player.camera_box.size() / 2.;
-                //let k = if player.controller_id == 1 { 0.8 } else { 0.2 };
-                players_amount += 1;
-                middle_point += camera_pox_middle; // * k;
+            for target in targets {
+                let camera_box_middle = target.point() + target.size() / 2.;
+                targets_amount += 1;
+                middle_point += camera_box_middle;
 
-                min = min.min(camera_pox_middle);
-                max = max.max(camera_pox_middle);
+                min = min.min(camera_box_middle);
+                max = max.max(camera_box_middle);
+            }
+            if targets_amount == 0 {
+                middle_point = self.bounds.point() + self.bounds.size() / 2.;
+            } else {
+                middle_point /= targets_amount as f32;
             }
-            middle_point /= players_amount as f32;
 
             let border_x = 150.;
             let border_y = 200.;
@@ -191,49 +196,60 @@ impl scene::Node for GameCamera {
             if scale.x > scale.y * aspect {
                 scale.y = scale.x / aspect;
             }
-            let mut zoom = scale.y;
+            // never show more than the whole arena
+            let mut zoom = scale.y.min(self.bounds.h) / zoom_scale;
 
             // bottom camera bound
-            if scale.y / 2. + middle_point.y > node.bounds.h {
-                middle_point.y = node.bounds.h - scale.y / 2.;
+            if zoom / 2. + middle_point.y > self.bounds.h {
+                middle_point.y = self.bounds.h - zoom / 2.;
             }
 
-            if let Some((override_target, override_zoom)) = node.manual {
+            if let Some((override_target, override_zoom)) = self.manual {
                 middle_point = override_target;
                 zoom = override_zoom;
             }
 
-            node.follow_buffer.insert(0, (middle_point, zoom));
-            node.follow_buffer.truncate(Self::BUFFER_CAPACITY);
+            self.follow_buffer.insert(0, (middle_point, zoom));
+            self.follow_buffer.truncate(Self::BUFFER_CAPACITY);
         }
         let mut sum_pos = (0.0f64, 0.0f64);
         let mut sum_zoom = 0.0;
-        for (pos, zoom) in &node.follow_buffer {
+        for (pos, zoom) in &self.follow_buffer {
             sum_pos.0 += pos.x as f64;
             sum_pos.1 += pos.y as f64;
             sum_zoom += *zoom as f64;
         }
         let mut middle_point = vec2(
-            (sum_pos.0 / node.follow_buffer.len() as f64) as f32,
-            (sum_pos.1 / node.follow_buffer.len() as f64) as f32,
+            (sum_pos.0 / self.follow_buffer.len() as f64) as f32,
+            (sum_pos.1 / self.follow_buffer.len() as f64) as f32,
         );
-        let zoom = (sum_zoom / node.follow_buffer.len() as f64) as f32;
+        let zoom = (sum_zoom / self.follow_buffer.len() as f64) as f32;
 
-        let shake = node.get_shake();
+        let shake = self.get_shake();
         middle_point += shake.0;
         let rotation = shake.1;
 
         let aspect = screen_width() / screen_height();
 
-        // let middle_point = vec2(400., 600.);
-        // let zoom = 400.;
-        let macroquad_camera = Camera2D {
+        self.camera = Camera2D {
             target: middle_point,
             zoom: vec2(1. / aspect, -1.) / zoom * 2.,
             rotation,
             ..Camera2D::default()
         };
+    }
+
+    /// The macroquad camera computed by the last call to `update`
+    pub fn get_camera(&self) -> &Camera2D {
+        &self.camera
+    }
+
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        self.camera.screen_to_world(point)
+    }
 
-        scene::set_camera(0, Some(macroquad_camera));
+    #[allow(dead_code)]
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        self.camera.world_to_screen(point)
     }
 }
@@ -0,0 +1,224 @@
+//! This implements the campaign, a world tour where the player's team travels from court to court,
+//! taking on the teams in `campaign.json` one after the other. The AI gets harder along the way,
+//! and some stages reward the player with a character from `player_characters.json`, that is
+//! locked until then.
+//!
+//! Progress is saved in the active profile after every win, which character selection also reads
+//! to tell which characters have been unlocked.
+
+use macroquad::prelude::collections::storage;
+use macroquad::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::ai::Difficulty;
+use crate::game::character::PlayerCharacterParams;
+use crate::game::match_options::{Controller, MatchOptions};
+use crate::game::match_state::MatchEnd;
+use crate::game::profile::{update_profile, Profile};
+use crate::game::resources::Resources;
+use crate::game::scene::menu;
+use crate::game::team::TeamParams;
+use crate::Team;
+
+/// A stage of the campaign, which is a match against a team on a court
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CampaignStageParams {
+    /// The id of the opponent team, from `teams.json`
+    pub opponent: String,
+    /// The id of the court, from `courts.json`
+    pub court: String,
+    /// How well the opponent team plays
+    pub difficulty: Difficulty,
+    /// The text shown before the match
+    #[serde(default)]
+    pub intro: String,
+    /// The id of a locked character, from `player_characters.json`, that is unlocked by winning
+    /// this stage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reward: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CampaignProgress {
+    /// The id of the team the player is touring with, from `teams.json`, if a tour was started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+    /// The index of the next stage to play
+    #[serde(default)]
+    pub stage: usize,
+    /// The ids of the characters that have been unlocked. These stay unlocked when a new tour is
+    /// started
+    #[serde(default)]
+    pub unlocked_characters: Vec<String>,
+}
+
+impl CampaignProgress {
+    /// The file, in the assets directory, that progress was saved to before there were profiles
+    pub const CAMPAIGN_PROGRESS_FILE: &'static str = "campaign_progress";
+
+    pub fn is_unlocked(&self, character: &PlayerCharacterParams) -> bool {
+        !character.locked || self.unlocked_characters.contains(&character.id)
+    }
+
+    /// Records a won stage, and unlocks its reward. Returns the id of the character that was
+    /// unlocked, if it was not unlocked already
+    pub fn complete_stage(&mut self, stage: &CampaignStageParams) -> Option<String> {
+        self.stage += 1;
+        let reward = stage.reward.clone()?;
+        if self.unlocked_characters.contains(&reward) {
+            return None;
+        }
+        self.unlocked_characters.push(reward.clone());
+        Some(reward)
+    }
+}
+
+/// The characters that can be picked, which leaves out the ones that are still locked
+pub fn available_characters(characters: &[PlayerCharacterParams]) -> Vec<PlayerCharacterParams> {
+    let profile = storage::get::<Profile>();
+    characters
+        .iter()
+        .filter(|character| profile.campaign.is_unlocked(character))
+        .cloned()
+        .collect()
+}
+
+const TEXT_SIZE: f32 = 24.;
+const LINE_HEIGHT: f32 = 30.;
+/// The number of characters an intro line is wrapped at
+const WRAP_WIDTH: usize = 60;
+
+/// Runs the campaign, until it is left with Escape, or the tour is over. `options` are used for
+/// every match, with the teams, court and AI of each stage swapped in. Returns whether a match was
+/// quit to the main menu
+pub async fn campaign(options: &MatchOptions) -> bool {
+    let (stages, teams, courts, characters) = {
+        let resources = storage::get::<Resources>();
+        (
+            resources.campaign.clone(),
+            resources.teams.clone(),
+            resources.courts.clone(),
+            resources.player_characters.clone(),
+        )
+    };
+    if stages.is_empty() || teams.is_empty() {
+        return false;
+    }
+    let mut progress = storage::get::<Profile>().campaign.clone();
+    let is_touring = progress.team.is_some() && progress.stage < stages.len();
+    if is_touring {
+        match menu("World tour", &["Continue tour", "New tour"]).await {
+            Some(0) => {}
+            Some(_) => progress.team = None,
+            None => return false,
+        }
+        next_frame().await;
+    }
+    if progress.team.is_none() || progress.stage >= stages.len() {
+        let names: Vec<&str> = teams.iter().map(|team| team.name.as_str()).collect();
+        match menu("Pick your team", &names).await {
+            Some(i) => {
+                progress.team = Some(teams[i].id.clone());
+                progress.stage = 0;
+            }
+            None => return false,
+        }
+        next_frame().await;
+    }
+    // shown above the intro, after a match
+    let mut message = String::new();
+    loop {
+        if is_key_pressed(KeyCode::Escape) {
+            return false;
+        }
+        if progress.stage >= stages.len() {
+            if is_key_pressed(KeyCode::Enter) {
+                return false;
+            }
+            clear_background(LIGHTGRAY);
+            draw_text("World champions!", 40., 80., TEXT_SIZE * 2., BLACK);
+            draw_text(&message, 40., 140., TEXT_SIZE, RED);
+            let txt = format!("{} have beaten every team on the tour.", team_name(&teams, progress.team.as_deref()));
+            draw_text(&txt, 40., 190., TEXT_SIZE, BLACK);
+            draw_text("Enter: back to the menu", 40., screen_height() - 30., TEXT_SIZE * 0.75, DARKGRAY);
+            next_frame().await;
+            continue;
+        }
+        let stage = &stages[progress.stage];
+        if is_key_pressed(KeyCode::Enter) {
+            let mut match_options = options.clone();
+            match_options.team_one = Controller::Human;
+            match_options.team_two = Controller::Cpu(stage.difficulty);
+            match_options.set_team(Team::One, progress.team.as_ref().and_then(|id| teams.iter().find(|team| &team.id == id)));
+            match_options.set_team(Team::Two, teams.iter().find(|team| team.id == stage.opponent));
+            match_options.court = stage.court.clone();
+            let result = match crate::local_game(&mut match_options).await {
+                MatchEnd::Finished(result) => result,
+                MatchEnd::QuitToMenu => return true,
+            };
+            if result.winner == Team::One {
+                message = match progress.complete_stage(stage) {
+                    Some(reward) => {
+                        let name = characters.iter()
+                            .find(|character| character.id == reward)
+                            .map_or(reward.as_str(), |character| character.name.as_str());
+                        format!("Victory! {} has been unlocked", name)
+                    }
+                    None => "Victory!".to_string(),
+                };
+                if let Err(err) = update_profile(|profile| profile.campaign = progress.clone()) {
+                    message = format!("{} (could not save the progress: {})", message, err);
+                }
+            } else {
+                message = "Defeat... try again!".to_string();
+            }
+            next_frame().await;
+            continue;
+        }
+
+        clear_background(LIGHTGRAY);
+        let title = format!("World tour: stage {} of {}", progress.stage + 1, stages.len());
+        draw_text(&title, 40., 50., TEXT_SIZE * 1.5, BLACK);
+        draw_text(&message, 40., 90., TEXT_SIZE, RED);
+        let court = courts.iter()
+            .find(|court| court.id == stage.court)
+            .map_or(stage.court.as_str(), |court| court.name.as_str());
+        let lines = [
+            format!("Opponent: {}", team_name(&teams, Some(&stage.opponent))),
+            format!("Court: {}", court),
+            format!("Difficulty: {}", stage.difficulty.as_str()),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, 40., 140. + LINE_HEIGHT * i as f32, TEXT_SIZE, BLACK);
+        }
+        for (i, line) in wrap(&stage.intro, WRAP_WIDTH).iter().enumerate() {
+            draw_text(line, 40., 260. + LINE_HEIGHT * i as f32, TEXT_SIZE, DARKGRAY);
+        }
+        draw_text("Enter: play  Escape: leave the tour", 40., screen_height() - 30., TEXT_SIZE * 0.75, DARKGRAY);
+        next_frame().await;
+    }
+}
+
+fn team_name(teams: &[TeamParams], id: Option<&str>) -> String {
+    id.map_or_else(String::new, |id| {
+        teams.iter()
+            .find(|team| team.id == id)
+            .map_or_else(|| id.to_string(), |team| team.name.clone())
+    })
+}
+
+/// Splits text into lines of at most `width` characters, breaking between words
+fn wrap(txt: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in txt.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
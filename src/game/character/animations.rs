@@ -80,6 +80,22 @@ pub struct PlayerAnimations {
 }
 
 impl PlayerAnimations {
+    /// The ids of the animations that every character must have, as they are looked up by
+    /// `PlayerAnimations::from(Vec<AnimationMetadata>)`
+    pub const REQUIRED_IDS: [&'static str; 11] = [
+        Player::IDLE_ANIMATION_ID,
+        Player::MOVE_ANIMATION_ID,
+        Player::JUMP_ANIMATION_ID,
+        Player::FALL_ANIMATION_ID,
+        Player::CROUCH_ANIMATION_ID,
+        Player::DEATH_BACK_ANIMATION_ID,
+        Player::DEATH_FACE_ANIMATION_ID,
+        Player::PUNCH_ANIMATION_ID,
+        Player::RUN_ANIMATION_ID,
+        Player::HURT_ANIMATION_ID,
+        Player::CATCH_ANIMATION_ID,
+    ];
+
     pub fn default_idle_animation() -> AnimationMetadata {
         AnimationMetadata {
             id: Player::IDLE_ANIMATION_ID.to_string(),
@@ -7,11 +7,13 @@ use macroquad::prelude::*;
 
 use serde::{Deserialize, Serialize};
 
+use crate::game::animations::{AnimationParams, AnimationPlayer};
+use crate::game::player::Player;
 use crate::json;
 
 mod animations;
 
-pub use animations::PlayerAnimationParams;
+pub use animations::{PlayerAnimationParams, PlayerAnimations};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerCharacterParams {
@@ -35,9 +37,16 @@ pub struct PlayerCharacterParams {
         with = "json::vec2_def"
     )]
     pub collider_size: Vec2,
-    /// This is the offset from the position of the player to where the weapon is mounted.
-    /// The position of the player will, typically, be the center bottom of the sprite but this
-    /// can be changed with offsets.
+    /// The offset of the collider from the bottom center of the sprite, which is where the bottom
+    /// center of the collider is by default. Use this to line the collider up with the feet and
+    /// body of the character. It is mirrored when the player is facing left.
+    #[serde(
+        default = "PlayerCharacterParams::default_collider_offset",
+        with = "json::vec2_def"
+    )]
+    pub collider_offset: Vec2,
+    /// This is the offset from the center of the player to where the ball is held and released,
+    /// when facing right. It is mirrored when the player is facing left.
     #[serde(
         default = "PlayerCharacterParams::default_weapon_mount",
         with = "json::vec2_def"
@@ -52,7 +61,7 @@ pub struct PlayerCharacterParams {
     /// This is the upwards force applied to the player character when it jumps
     #[serde(default = "PlayerCharacterParams::default_jump_force")]
     pub jump_force: f32,
-    /// This is the movement speed of the player character
+    /// This is the walking speed of the player character, in pixels per second
     #[serde(default = "PlayerCharacterParams::default_move_speed")]
     pub move_speed: f32,
     /// This is the slide speed factor of the player character
@@ -64,6 +73,9 @@ pub struct PlayerCharacterParams {
     /// This is the float gravity factor of the player character
     #[serde(default = "PlayerCharacterParams::default_float_gravity_factor")]
     pub float_gravity_factor: f32,
+    /// A locked player character can not be picked, until it is unlocked in the campaign
+    #[serde(default, skip_serializing_if = "json::is_false")]
+    pub locked: bool,
 }
 
 impl PlayerCharacterParams {
@@ -79,8 +91,8 @@ impl PlayerCharacterParams {
     const DEFAULT_COLLIDER_WIDTH: f32 = 20.0;
     const DEFAULT_COLLIDER_HEIGHT: f32 = 54.0;
 
-    const DEFAULT_WEAPON_MOUNT_X: f32 = -8.0;
-    const DEFAULT_WEAPON_MOUNT_Y: f32 = 25.75;
+    const DEFAULT_WEAPON_MOUNT_X: f32 = 40.0;
+    const DEFAULT_WEAPON_MOUNT_Y: f32 = -11.0;
 
     pub fn default_head_threshold() -> f32 {
         Self::DEFAULT_HEAD_THRESHOLD
@@ -114,7 +126,21 @@ impl PlayerCharacterParams {
         vec2(Self::DEFAULT_COLLIDER_WIDTH, Self::DEFAULT_COLLIDER_HEIGHT)
     }
 
+    pub fn default_collider_offset() -> Vec2 {
+        Vec2::ZERO
+    }
+
     pub fn default_weapon_mount() -> Vec2 {
         vec2(Self::DEFAULT_WEAPON_MOUNT_X, Self::DEFAULT_WEAPON_MOUNT_Y)
     }
+
+    /// Makes the animation player of a player with this character, playing the idle animation.
+    /// This panics if the texture of the character is not in `Resources`
+    pub fn animation_player(&self) -> AnimationPlayer {
+        let params: AnimationParams = self.animation.clone().into();
+        let mut animation_player = AnimationPlayer::new(params);
+        animation_player.set_animation(Player::IDLE_ANIMATION_ID);
+        animation_player.set_scale(1.);
+        animation_player
+    }
 }
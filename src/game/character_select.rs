@@ -0,0 +1,205 @@
+//! This implements the character selection screen, where both teams pick a team from `teams.json`
+//! and a character for every slot of their roster, before a match starts. The picks are stored in
+//! `MatchOptions`, which `new_game` reads from when it builds the players.
+
+use macroquad::prelude::collections::storage;
+use macroquad::prelude::*;
+use macroquad::rand::gen_range;
+
+use crate::game::animations::{AnimationParams, AnimationPlayer};
+use crate::game::campaign::available_characters;
+use crate::game::character::PlayerCharacterParams;
+use crate::game::match_options::MatchOptions;
+use crate::game::resources::Resources;
+use crate::game::team::{draw_emblem, TeamParams};
+use crate::{Player, Team, INFIELDERS_PER_TEAM, OUTFIELDERS_PER_TEAM};
+
+const RANDOM_KEY: KeyCode = KeyCode::R;
+const RANDOM_ALL_KEY: KeyCode = KeyCode::T;
+const PREVIEW_ANIMATION_KEY: KeyCode = KeyCode::Tab;
+
+/// The animations the preview cycles through
+const PREVIEW_ANIMATIONS: [&str; 5] = [
+    Player::IDLE_ANIMATION_ID,
+    Player::MOVE_ANIMATION_ID,
+    Player::RUN_ANIMATION_ID,
+    Player::CROUCH_ANIMATION_ID,
+    Player::HURT_ANIMATION_ID,
+];
+
+const TEXT_SIZE: f32 = 24.;
+const LINE_HEIGHT: f32 = 30.;
+const PREVIEW_SCALE: f32 = 2.;
+
+/// Shows the character selection screen, until the match is started with Enter, or the screen is
+/// left with Escape. Returns true if the match should be started.
+pub async fn character_select(options: &mut MatchOptions) -> bool {
+    let (characters, teams) = {
+        let resources = storage::get::<Resources>();
+        (available_characters(&resources.player_characters), resources.teams.clone())
+    };
+    if characters.is_empty() {
+        return true;
+    }
+    let mut previews: Vec<AnimationPlayer> = characters
+        .iter()
+        .map(|character| {
+            let animation_params: AnimationParams = character.animation.clone().into();
+            let mut animation_player = AnimationPlayer::new(animation_params);
+            animation_player.set_animation(Player::IDLE_ANIMATION_ID);
+            animation_player.set_scale(PREVIEW_SCALE);
+            animation_player
+        })
+        .collect();
+    let roster_size = INFIELDERS_PER_TEAM + OUTFIELDERS_PER_TEAM;
+    // every team has a row for picking the team, followed by a row for every slot, team one's first
+    let rows_per_team = roster_size + 1;
+    let rows = rows_per_team * 2;
+    let mut selected_row = 0;
+    let mut preview_animation = 0;
+    loop {
+        if is_key_pressed(KeyCode::Escape) {
+            return false;
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            return true;
+        }
+        if is_key_pressed(KeyCode::Up) {
+            selected_row = (selected_row + rows - 1) % rows;
+        }
+        if is_key_pressed(KeyCode::Down) {
+            selected_row = (selected_row + 1) % rows;
+        }
+        let (team, slot) = row_to_slot(selected_row, rows_per_team);
+        match slot {
+            None => {
+                // the first choice is no team at all
+                let current = team_index(&teams, options.team_id(team));
+                let choices = teams.len() + 1;
+                let choice = if is_key_pressed(KeyCode::Left) {
+                    Some((current + choices - 1) % choices)
+                } else if is_key_pressed(KeyCode::Right) {
+                    Some((current + 1) % choices)
+                } else if is_key_pressed(RANDOM_KEY) {
+                    Some(gen_range(0, choices))
+                } else {
+                    None
+                };
+                if let Some(choice) = choice {
+                    options.set_team(team, choice.checked_sub(1).map(|i| &teams[i]));
+                }
+            }
+            Some(slot) => {
+                let current = character_index(&characters, options.character(team, slot));
+                if is_key_pressed(KeyCode::Left) {
+                    let previous = (current + characters.len() - 1) % characters.len();
+                    options.set_character(team, slot, &characters[previous].id);
+                }
+                if is_key_pressed(KeyCode::Right) {
+                    let next = (current + 1) % characters.len();
+                    options.set_character(team, slot, &characters[next].id);
+                }
+                if is_key_pressed(RANDOM_KEY) {
+                    let random = gen_range(0, characters.len());
+                    options.set_character(team, slot, &characters[random].id);
+                }
+            }
+        }
+        if is_key_pressed(RANDOM_ALL_KEY) {
+            for team in [Team::One, Team::Two] {
+                for slot in 0..roster_size {
+                    let random = gen_range(0, characters.len());
+                    options.set_character(team, slot, &characters[random].id);
+                }
+            }
+        }
+        if is_key_pressed(PREVIEW_ANIMATION_KEY) {
+            preview_animation = (preview_animation + 1) % PREVIEW_ANIMATIONS.len();
+            for preview in previews.iter_mut() {
+                preview.set_animation(PREVIEW_ANIMATIONS[preview_animation]);
+            }
+        }
+
+        clear_background(LIGHTGRAY);
+        draw_text("Character select", 40., 50., TEXT_SIZE * 1.5, BLACK);
+        for (team, x) in [(Team::One, 40.), (Team::Two, screen_width() - 300.)] {
+            let params = options.team_id(team).and_then(|id| teams.iter().find(|params| params.id == id));
+            let header = match (team, params) {
+                (_, Some(params)) => params.name.clone(),
+                (Team::One, None) => "Team one".to_string(),
+                (Team::Two, None) => "Team two".to_string(),
+            };
+            let is_selected = row_to_slot(selected_row, rows_per_team) == (team, None);
+            let txt = format!("{}{}", if is_selected { "> " } else { "" }, header);
+            let color = params.map_or(BLACK, |params| params.primary_color);
+            draw_text(&txt, x, 110., TEXT_SIZE, if is_selected { RED } else { color });
+            if let Some(params) = params {
+                draw_emblem(params, &storage::get::<Resources>().textures, vec2(x - 36., 86.), 32.);
+            }
+            for slot in 0..roster_size {
+                let character = &characters[character_index(&characters, options.character(team, slot))];
+                let is_selected = row_to_slot(selected_row, rows_per_team) == (team, Some(slot));
+                let txt = format!("{}{}: {}", if is_selected { "> " } else { "" }, slot_label(slot), character.name);
+                let y = 110. + LINE_HEIGHT * (slot + 1) as f32;
+                draw_text(&txt, x, y, TEXT_SIZE, if is_selected { RED } else { BLACK });
+            }
+        }
+
+        let current = character_index(&characters, options.character(team, slot.unwrap_or(0)));
+        let preview = &mut previews[current];
+        preview.update();
+        let preview_position = vec2(screen_width() / 2. - 64., 120.);
+        preview.draw(preview_position, 0., team == Team::Two, false);
+        draw_character_info(&characters[current], vec2(screen_width() / 2. - 150., 300.));
+
+        let help = "Up/Down: slot  Left/Right: team or character  R: random  T: randomize all  Tab: animation  Enter: start  Escape: back";
+        draw_text(help, 40., screen_height() - 30., TEXT_SIZE * 0.75, DARKGRAY);
+        next_frame().await;
+    }
+}
+
+/// Draws the name, description and stats of a character
+fn draw_character_info(character: &PlayerCharacterParams, position: Vec2) {
+    let lines = [
+        character.name.clone(),
+        character.description.clone(),
+        format!("Speed: {}", character.move_speed),
+        format!("Size: {} x {}", character.collider_size.x, character.collider_size.y),
+        format!("Reach: {}", character.weapon_mount.x),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        let size = if i == 0 { TEXT_SIZE * 1.25 } else { TEXT_SIZE };
+        draw_text(line, position.x, position.y + LINE_HEIGHT * i as f32, size, BLACK);
+    }
+}
+
+/// Returns the team a row belongs to, and the roster slot, or `None` for the team's own row
+fn row_to_slot(row: usize, rows_per_team: usize) -> (Team, Option<usize>) {
+    let (team, row) = if row < rows_per_team {
+        (Team::One, row)
+    } else {
+        (Team::Two, row - rows_per_team)
+    };
+    (team, row.checked_sub(1))
+}
+
+/// The index of the team with the given id, counting no team as the first choice
+fn team_index(teams: &[TeamParams], id: Option<&str>) -> usize {
+    id.and_then(|id| teams.iter().position(|params| params.id == id))
+        .map_or(0, |i| i + 1)
+}
+
+/// The index of the character with the given id, or the first character, if there is no such
+/// character
+fn character_index(characters: &[PlayerCharacterParams], id: Option<&str>) -> usize {
+    id.and_then(|id| characters.iter().position(|character| character.id == id))
+        .unwrap_or(0)
+}
+
+fn slot_label(slot: usize) -> String {
+    if slot < INFIELDERS_PER_TEAM {
+        format!("Infielder {}", slot + 1)
+    } else {
+        format!("Outfielder {}", slot - INFIELDERS_PER_TEAM + 1)
+    }
+}
@@ -0,0 +1,97 @@
+//! This implements `CourtParams`, which is a declaration of a court that matches are played on,
+//! loaded from the `courts.json` file. A court changes how the field looks, and has its own music.
+//! It can also put obstacles on the field, which players have to walk around.
+
+use macroquad::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::field::Field;
+use crate::json;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourtParams {
+    /// This is the id of the court. This should be unique, as courts are looked up by it
+    pub id: String,
+    /// This is the name of the court, as shown in menus
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// The color of the court itself
+    #[serde(default = "CourtParams::default_floor_color", with = "json::ColorDef")]
+    pub floor_color: Color,
+    /// The color of the lines marking the edges and the mid line of the court
+    #[serde(default = "CourtParams::default_line_color", with = "json::ColorDef")]
+    pub line_color: Color,
+    /// The color around the court
+    #[serde(default = "CourtParams::default_background_color", with = "json::ColorDef")]
+    pub background_color: Color,
+    /// The ids of the music played on the court, from `sounds.json`. A round plays the next track
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub music: Vec<String>,
+    /// Props standing on the field, that players can not walk through
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub obstacles: Vec<ObstacleParams>,
+}
+
+impl CourtParams {
+    const DEFAULT_FLOOR_COLOR: Color = Color::new(0.76, 0.7, 0.6, 1.);
+    const DEFAULT_LINE_COLOR: Color = WHITE;
+    const DEFAULT_BACKGROUND_COLOR: Color = LIGHTGRAY;
+
+    pub fn default_floor_color() -> Color {
+        Self::DEFAULT_FLOOR_COLOR
+    }
+
+    pub fn default_line_color() -> Color {
+        Self::DEFAULT_LINE_COLOR
+    }
+
+    pub fn default_background_color() -> Color {
+        Self::DEFAULT_BACKGROUND_COLOR
+    }
+}
+
+impl Default for CourtParams {
+    fn default() -> Self {
+        CourtParams {
+            id: String::new(),
+            name: "Court".to_string(),
+            description: String::new(),
+            floor_color: Self::default_floor_color(),
+            line_color: Self::default_line_color(),
+            background_color: Self::default_background_color(),
+            music: Vec::new(),
+            obstacles: Vec::new(),
+        }
+    }
+}
+
+/// A prop on the field, like a crate or a pillar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObstacleParams {
+    /// The center of the obstacle, as a fraction of the court, where 0, 0 is the top left corner
+    /// and 1, 1 is the bottom right corner
+    #[serde(with = "json::vec2_def")]
+    pub pos: Vec2,
+    /// The size of the obstacle, in pixels
+    #[serde(with = "json::vec2_def")]
+    pub size: Vec2,
+    #[serde(default = "ObstacleParams::default_color", with = "json::ColorDef")]
+    pub color: Color,
+}
+
+impl ObstacleParams {
+    const DEFAULT_COLOR: Color = DARKBROWN;
+
+    pub fn default_color() -> Color {
+        Self::DEFAULT_COLOR
+    }
+
+    /// The area the obstacle takes up on `field`
+    pub fn rect(&self, field: &Field) -> Rect {
+        let court_size = field.bottom_right - field.top_left;
+        let center = field.top_left + self.pos * court_size;
+        Rect::new(center.x - self.size.x / 2., center.y - self.size.y / 2., self.size.x, self.size.y)
+    }
+}
@@ -0,0 +1,74 @@
+//! This implements `GameEvent`, which tells what happened during a frame of play. The physics and
+//! rules code in `Game` only emits events, and everything that reacts to them, like particles,
+//! screen shake and stats, is a `GameEventListener`, that gets every event once the frame is over.
+//!
+//! The events of the last frame are kept in `Game::events` until the next frame starts, so code
+//! outside of `Game` can look at them as well.
+
+use macroquad::prelude::*;
+
+use crate::Team;
+
+/// The part of a player's body that a ball hit
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum HitZone {
+    Head,
+    Body,
+    Legs,
+}
+
+impl HitZone {
+    /// The zone of a collider that a ball at the height `y` hits. The top quarter is the head, and
+    /// the bottom third are the legs
+    pub fn at(y: f32, collider: Rect) -> Self {
+        let height = (y - collider.y) / collider.h;
+        if height < 0.25 {
+            HitZone::Head
+        } else if height < 2. / 3. {
+            HitZone::Body
+        } else {
+            HitZone::Legs
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    /// A ball was thrown at the other team
+    BallThrown { ball: usize, thrower: usize, super_shot: bool },
+    /// A ball was thrown to a teammate
+    BallPassed { ball: usize, passer: usize },
+    /// A player took hold of a flying ball. `from_opponent` is false for a caught pass
+    BallCaught { ball: usize, player: usize, from_opponent: bool, pos: Vec2 },
+    /// A player took hold of a ball that was lying around
+    BallPickedUp { ball: usize, player: usize },
+    /// A flying ball bounced off a player who was trying to catch it
+    BallFumbled { ball: usize, player: usize },
+    /// A ball thrown by the other team flew over a ducking player
+    BallDodged { ball: usize, player: usize },
+    /// A ball thrown by the other team hit a player. `velocity` and `max_speed` are those of the
+    /// ball, which tell how hard the hit was
+    PlayerHit {
+        ball: usize,
+        player: usize,
+        thrower: Option<usize>,
+        zone: HitZone,
+        damage: i32,
+        pos: Vec2,
+        velocity: Vec2,
+        max_speed: f32,
+    },
+    /// A ball bounced off the edge of the court
+    BallOutOfBounds { ball: usize, pos: Vec2 },
+    /// A player ran out of life. `by` is whoever threw the ball that knocked them out
+    PlayerEliminated { player: usize, by: Option<usize> },
+    /// A moving player took a step, which kicks up dust. `pos` is where their feet are. This is
+    /// sent every `Player::DUST_INTERVAL` while they keep moving
+    Footstep { player: usize, pos: Vec2 },
+    /// A team won the round
+    RoundWon { team: Team },
+}
+
+pub trait GameEventListener {
+    fn on_event(&mut self, event: &GameEvent);
+}
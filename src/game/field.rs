@@ -1,5 +1,5 @@
 use macroquad::prelude::*;
-use crate::{Player, PLAYER_HEIGHT, PLAYER_WIDTH};
+use crate::{Player, PLAYER_HEIGHT, PLAYER_WIDTH, Team};
 
 pub struct Field {
     pub(crate) top_left: Vec2,
@@ -16,11 +16,21 @@ pub struct Field {
 }
 
 impl Field {
+    /// The depth of the area behind the back lines, where the outfielders stand
+    pub(crate) const OUTFIELD_DEPTH: f32 = 40.;
+    /// The thickness of the walls around the area players can move in
+    const WALL_THICKNESS: f32 = 10.;
+
     pub(crate) fn default() -> Self {
-        let top_edge = screen_height() / 3.;
-        let left_edge = screen_width() / 10.;
-        let bottom_edge = screen_height() - screen_height() / 4.;
-        let right_edge = screen_width() - screen_width() / 10.;
+        Self::new(vec2(screen_width(), screen_height()))
+    }
+
+    /// Lays out the field on a screen of the given size
+    pub(crate) fn new(screen_size: Vec2) -> Self {
+        let top_edge = screen_size.y / 3.;
+        let left_edge = screen_size.x / 10.;
+        let bottom_edge = screen_size.y - screen_size.y / 4.;
+        let right_edge = screen_size.x - screen_size.x / 10.;
 
         let top_left = Vec2::new(left_edge, top_edge);
         let bottom_left = Vec2::new(left_edge, bottom_edge);
@@ -52,6 +62,45 @@ impl Field {
         false
     }
 
+    /// The area that the position of a player of `team` must stay inside. Infielders stay in their
+    /// half of the court, while outfielders stay behind the back line of the other team.
+    pub(crate) fn player_zone(&self, team: Team, is_outfielder: bool) -> Rect {
+        let top = self.top_edge - PLAYER_HEIGHT / 2.;
+        let bottom = self.bottom_edge - PLAYER_HEIGHT;
+        let (left, right) = match (team, is_outfielder) {
+            (Team::One, false) => (self.left_edge, self.mid_section - PLAYER_WIDTH),
+            (Team::Two, false) => (self.mid_section, self.right_edge - PLAYER_WIDTH),
+            (Team::One, true) => (self.right_edge, self.right_edge + Self::OUTFIELD_DEPTH),
+            (Team::Two, true) => (
+                self.left_edge - PLAYER_WIDTH - Self::OUTFIELD_DEPTH,
+                self.left_edge - PLAYER_WIDTH,
+            ),
+        };
+        Rect::new(left, top, right - left, bottom - top)
+    }
+
+    /// The walls that keep players in their zones, as solids for the `World`. They go around the
+    /// whole area that players can be in, including the outfields, and along the back lines and
+    /// the mid line, which split it up into the zones of `player_zone`.
+    pub(crate) fn walls(&self) -> Vec<Rect> {
+        let top = self.top_edge - PLAYER_HEIGHT / 2.;
+        let bottom = self.bottom_edge;
+        let left = self.left_edge - PLAYER_WIDTH - Self::OUTFIELD_DEPTH;
+        let right = self.right_edge + PLAYER_WIDTH + Self::OUTFIELD_DEPTH;
+        let thickness = Self::WALL_THICKNESS;
+        let mut walls = vec![
+            Rect::new(left - thickness, top - thickness, right - left + thickness * 2., thickness),
+            Rect::new(left - thickness, bottom, right - left + thickness * 2., thickness),
+            Rect::new(left - thickness, top, thickness, bottom - top),
+            Rect::new(right, top, thickness, bottom - top),
+        ];
+        // the lines are thin, so that they take as little room from the zones as they can
+        for x in [self.left_edge, self.mid_section, self.right_edge] {
+            walls.push(Rect::new(x - 1., top, 2., bottom - top));
+        }
+        walls
+    }
+
     pub(crate) fn player_outside_field(&self, player: &Player) -> bool {
         if player.pos.x > self.right_edge { return true; }
         if player.pos.x + PLAYER_WIDTH < self.left_edge { return true; }
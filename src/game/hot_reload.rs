@@ -0,0 +1,145 @@
+//! This implements hot reloading of resources, so that they can be tuned while the game is running.
+//! `ResourceWatcher` polls the modification times of the resource files, and of the texture files,
+//! reloads the files that changed into `Resources`, and swaps the changes into the round that is
+//! being played.
+//!
+//! A file that can not be loaded, because of a parse error for example, is reported with a toast,
+//! instead of stopping the game, and the resources that were loaded from it before are kept.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use macroquad::prelude::collections::storage;
+use macroquad::prelude::*;
+
+use crate::game::resources::{reload_resource_file, Resources};
+use crate::game::Game;
+
+/// A message shown at the bottom of the screen for a while
+struct Toast {
+    text: String,
+    is_error: bool,
+    /// The time the toast was shown at
+    time: f64,
+}
+
+impl Toast {
+    const DURATION: f64 = 4.;
+    /// Errors stay up for longer, so that there is time to read them
+    const ERROR_DURATION: f64 = 10.;
+    const TEXT_SIZE: f32 = 20.;
+
+    fn is_expired(&self) -> bool {
+        let duration = if self.is_error { Self::ERROR_DURATION } else { Self::DURATION };
+        get_time() - self.time > duration
+    }
+
+    fn draw(&self) {
+        let size = measure_text(&self.text, None, Self::TEXT_SIZE as u16, 1.);
+        let x = (screen_width() - size.width) / 2.;
+        let y = screen_height() - 90.;
+        let color = if self.is_error { RED } else { DARKGREEN };
+        draw_rectangle(x - 10., y - size.height - 8., size.width + 20., size.height + 16., Color::new(0., 0., 0., 0.7));
+        draw_text(&self.text, x, y, Self::TEXT_SIZE, color);
+    }
+}
+
+pub struct ResourceWatcher {
+    /// The last known modification time of every watched file, by path
+    modified: HashMap<PathBuf, SystemTime>,
+    last_poll: f64,
+    toast: Option<Toast>,
+}
+
+impl ResourceWatcher {
+    /// The time, in seconds, between checks for changed files
+    const POLL_INTERVAL: f64 = 0.5;
+
+    /// Starts watching the files of the `Resources` in storage, as they are now
+    pub fn new() -> Self {
+        let mut watcher = ResourceWatcher {
+            modified: HashMap::new(),
+            last_poll: get_time(),
+            toast: None,
+        };
+        watcher.changed_files();
+        watcher
+    }
+
+    /// Reloads the resource files that changed since the last check, and applies them to `game`
+    pub async fn update(&mut self, game: &mut Game) {
+        if self.toast.as_ref().is_some_and(Toast::is_expired) {
+            self.toast = None;
+        }
+        if get_time() - self.last_poll < Self::POLL_INTERVAL {
+            return;
+        }
+        self.last_poll = get_time();
+        let changed = self.changed_files();
+        if changed.is_empty() {
+            return;
+        }
+        let mut errors = Vec::new();
+        for file in &changed {
+            if let Err(err) = reload_resource_file(file).await {
+                errors.push(format!("Could not reload '{}': {}", file, err));
+            }
+        }
+        if let Err(err) = game.apply_resources() {
+            errors.push(format!("Could not apply the reloaded resources: {}", err));
+        }
+        let (text, is_error) = if errors.is_empty() {
+            (format!("Reloaded {}", changed.join(", ")), false)
+        } else {
+            (errors.join(" / "), true)
+        };
+        self.toast = Some(Toast { text, is_error, time: get_time() });
+    }
+
+    pub fn draw(&self) {
+        if let Some(toast) = &self.toast {
+            toast.draw();
+        }
+    }
+
+    /// The resource files that have to be reloaded, because they, or one of their textures,
+    /// changed since the last check. Files that were not watched before are only recorded
+    fn changed_files(&mut self) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        for (path, file) in watched_files() {
+            let modified = match fs::metadata(&path).and_then(|metadata| metadata.modified()) {
+                Ok(modified) => modified,
+                // the file may be in the middle of being saved, so it is checked again next time
+                Err(_) => continue,
+            };
+            let previous = self.modified.insert(path, modified);
+            if previous.is_some_and(|time| time != modified) && !changed.contains(&file) {
+                changed.push(file);
+            }
+        }
+        changed
+    }
+}
+
+impl Default for ResourceWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Every file that resources are loaded from, with the resource file it belongs to. Textures
+/// belong to the textures file, which has to be reloaded when they change
+fn watched_files() -> Vec<(PathBuf, &'static str)> {
+    let resources = storage::get::<Resources>();
+    let assets_dir = resources.assets_dir.as_str();
+    let files = Resources::FILES
+        .iter()
+        .map(|&file| (Resources::file_path(assets_dir, file), file));
+    let textures = resources
+        .textures
+        .values()
+        .map(|texture| (Path::new(assets_dir).join(&texture.meta.path), Resources::TEXTURES_FILE));
+    files.chain(textures).collect()
+}
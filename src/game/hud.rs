@@ -0,0 +1,138 @@
+//! This implements the HUD, which shows the state of a match on top of the court. It is drawn in
+//! screen space, after the world, so it stays anchored to the corners of the screen no matter
+//! where the camera is, or how far it is zoomed in.
+//!
+//! Team one's panel is in the top left corner, and team two's is mirrored in the top right. Each
+//! panel has the team's emblem, name and round wins, followed by a life bar for every player, with
+//! the shot clock next to the player holding a ball. The round timer, or the time played if rounds
+//! have no time limit, and the round are in the top center.
+
+use macroquad::prelude::collections::storage;
+use macroquad::prelude::*;
+
+use crate::game::match_state::MatchState;
+use crate::game::resources::Resources;
+use crate::game::team::draw_emblem;
+use crate::game::Game;
+use crate::Team;
+
+const MARGIN: f32 = 16.;
+const EMBLEM_SIZE: f32 = 32.;
+const NAME_SIZE: f32 = 26.;
+const TEXT_SIZE: f32 = 18.;
+const BAR_WIDTH: f32 = 140.;
+const BAR_HEIGHT: f32 = 10.;
+const BAR_SPACING: f32 = 26.;
+const PIP_RADIUS: f32 = 6.;
+const POSSESSION_RADIUS: f32 = 5.;
+const PANEL_WIDTH: f32 = 240.;
+/// The time, in seconds, left on the round timer, from which it is drawn in red
+const WARNING_TIME: f32 = 10.;
+const SHOT_CLOCK_WARNING_TIME: f32 = 3.;
+
+/// The color of a life bar, by how much of the player's life is left
+pub fn calculate_life_color(life: i32, max_life: i32) -> Color {
+    let percent = life * 100 / max_life.max(1);
+    if percent > 70 {
+        DARKGREEN
+    } else if percent > 40 {
+        YELLOW
+    } else {
+        RED
+    }
+}
+
+pub fn draw_hud(game: &Game, match_state: &MatchState) {
+    for team in [Team::One, Team::Two] {
+        draw_team_panel(game, match_state, team);
+    }
+    let (seconds, color) = match match_state.round_time {
+        // the countdown turns red for the last few seconds
+        Some(_) if match_state.round_time_left <= WARNING_TIME => (match_state.round_time_left.ceil() as u32, RED),
+        Some(_) => (match_state.round_time_left.ceil() as u32, BLACK),
+        None => (match_state.time_played as u32, BLACK),
+    };
+    let txt = format!("{:02}:{:02}", seconds / 60, seconds % 60);
+    draw_centered_text(&txt, MARGIN + NAME_SIZE, NAME_SIZE * 1.25, color);
+    let txt = format!("Round {}", match_state.round);
+    draw_centered_text(&txt, MARGIN + NAME_SIZE + TEXT_SIZE * 1.25, TEXT_SIZE, DARKGRAY);
+    if match_state.sudden_death {
+        draw_centered_text("Sudden death!", MARGIN + NAME_SIZE + TEXT_SIZE * 2.75, NAME_SIZE, RED);
+    }
+}
+
+fn draw_team_panel(game: &Game, match_state: &MatchState, team: Team) {
+    // everything is laid out from the panel's outer edge, towards the center of the screen
+    let (left, direction) = match team {
+        Team::One => (MARGIN, 1.),
+        Team::Two => (screen_width() - MARGIN, -1.),
+    };
+    let x = |offset: f32, width: f32| {
+        if direction > 0. {
+            left + offset
+        } else {
+            left - offset - width
+        }
+    };
+    let color = game.team_color(team);
+    let has_ball = game.team_range(team).any(|i| game.ball_held_by(i).is_some());
+
+    if let Some(params) = game.teams.get(&team) {
+        draw_emblem(params, &storage::get::<Resources>().textures, vec2(x(0., EMBLEM_SIZE), MARGIN), EMBLEM_SIZE);
+    }
+    let name = game.team_name(team);
+    let name_width = measure_text(&name, None, NAME_SIZE as u16, 1.).width;
+    let name_x = x(EMBLEM_SIZE + 8., name_width);
+    draw_text(&name, name_x, MARGIN + NAME_SIZE * 0.8, NAME_SIZE, color);
+    if has_ball {
+        let ball_x = x(EMBLEM_SIZE + 8. + name_width + 10., 0.);
+        draw_circle(ball_x, MARGIN + NAME_SIZE * 0.5, POSSESSION_RADIUS, BLACK);
+    }
+
+    // a pip for every round the match can take to win, filled for the rounds this team won
+    let pips_y = MARGIN + EMBLEM_SIZE + PIP_RADIUS + 4.;
+    for i in 0..match_state.rounds_to_win {
+        let pip_x = x(PIP_RADIUS + (PIP_RADIUS * 2. + 4.) * i as f32, 0.);
+        if i < match_state.wins(team) {
+            draw_circle(pip_x, pips_y, PIP_RADIUS, GOLD);
+        }
+        draw_circle_lines(pip_x, pips_y, PIP_RADIUS, 2., BLACK);
+    }
+
+    let bars_y = pips_y + PIP_RADIUS + 8.;
+    for (row, i) in game.team_range(team).enumerate() {
+        let player = &game.players[i];
+        let y = bars_y + BAR_SPACING * row as f32;
+        let label = if player.is_outfielder {
+            format!("{} (out)", player.name)
+        } else {
+            player.name.clone()
+        };
+        let label_width = measure_text(&label, None, TEXT_SIZE as u16, 1.).width;
+        let label_color = if player.life > 0 || player.is_outfielder { BLACK } else { GRAY };
+        draw_text(&label, x(0., label_width), y + TEXT_SIZE * 0.6, TEXT_SIZE, label_color);
+        let bar_x = x(PANEL_WIDTH - BAR_WIDTH, BAR_WIDTH);
+        let bar_y = y + (TEXT_SIZE * 0.6 - BAR_HEIGHT) / 2.;
+        let filled = (player.life.max(0) as f32 / player.max_life.max(1) as f32).min(1.);
+        draw_rectangle(bar_x, bar_y, BAR_WIDTH, BAR_HEIGHT, DARKGRAY);
+        // team two's bars drain towards the outer edge, mirroring team one's
+        let fill_x = if direction > 0. { bar_x } else { bar_x + BAR_WIDTH * (1. - filled) };
+        draw_rectangle(fill_x, bar_y, BAR_WIDTH * filled, BAR_HEIGHT, calculate_life_color(player.life, player.max_life));
+        draw_rectangle_lines(bar_x, bar_y, BAR_WIDTH, BAR_HEIGHT, 2., BLACK);
+        if let Some(ball_index) = game.ball_held_by(i) {
+            let ball_x = x(PANEL_WIDTH + 10., 0.);
+            draw_circle(ball_x, bar_y + BAR_HEIGHT / 2., POSSESSION_RADIUS, BLACK);
+            if let Some(time_left) = match_state.shot_clock_left(ball_index) {
+                let txt = format!("{}", time_left.ceil() as u32);
+                let txt_width = measure_text(&txt, None, TEXT_SIZE as u16, 1.).width;
+                let color = if time_left <= SHOT_CLOCK_WARNING_TIME { RED } else { BLACK };
+                draw_text(&txt, x(PANEL_WIDTH + 20., txt_width), y + TEXT_SIZE * 0.6, TEXT_SIZE, color);
+            }
+        }
+    }
+}
+
+fn draw_centered_text(txt: &str, y: f32, size: f32, color: Color) {
+    let dimensions = measure_text(txt, None, size as u16, 1.);
+    draw_text(txt, (screen_width() - dimensions.width) / 2., y, size, color);
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+use crate::PlayerAction;
+
+/// The buttons held by whoever controls a player, for one frame. Human players produce this from
+/// their key set, while the AI fills it in directly, so both go through the same gameplay code.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PlayerInput {
+    pub up: bool,
+    pub right: bool,
+    pub down: bool,
+    pub left: bool,
+    /// Duck, or pass while holding the ball
+    pub a: bool,
+    /// Throw while holding the ball, or catch
+    pub b: bool,
+    /// Take control of the next teammate. This is only set on the frame the key goes down
+    pub switch: bool,
+    /// The player to throw or pass at. If this is `None`, the nearest one will be used
+    pub target: Option<usize>,
+    /// This is added to the angle of a throw, in radians, to make the AI miss
+    pub aim_offset: f32,
+}
+
+impl PlayerInput {
+    pub fn from_keys(keys: &HashMap<PlayerAction, KeyCode>) -> Self {
+        PlayerInput {
+            up: is_key_down(keys[&PlayerAction::MoveUp]),
+            right: is_key_down(keys[&PlayerAction::MoveRight]),
+            down: is_key_down(keys[&PlayerAction::MoveDown]),
+            left: is_key_down(keys[&PlayerAction::MoveLeft]),
+            a: is_key_down(keys[&PlayerAction::A]),
+            b: is_key_down(keys[&PlayerAction::B]),
+            switch: is_key_pressed(keys[&PlayerAction::Switch]),
+            ..Default::default()
+        }
+    }
+
+    pub fn movement(&self) -> [bool; 4] {
+        [self.up, self.right, self.down, self.left]
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.up || self.right || self.down || self.left
+    }
+
+    pub fn is_pressed(&self, action: &PlayerAction) -> bool {
+        match action {
+            PlayerAction::MoveUp => self.up,
+            PlayerAction::MoveRight => self.right,
+            PlayerAction::MoveDown => self.down,
+            PlayerAction::MoveLeft => self.left,
+            PlayerAction::A => self.a,
+            PlayerAction::B => self.b,
+            PlayerAction::Switch => self.switch,
+        }
+    }
+
+    /// Presses the button that corresponds with `action`
+    pub fn press(&mut self, action: &PlayerAction) {
+        match action {
+            PlayerAction::MoveUp => self.up = true,
+            PlayerAction::MoveRight => self.right = true,
+            PlayerAction::MoveDown => self.down = true,
+            PlayerAction::MoveLeft => self.left = true,
+            PlayerAction::A => self.a = true,
+            PlayerAction::B => self.b = true,
+            PlayerAction::Switch => self.switch = true,
+        }
+    }
+}
+
+/// The names keys are saved under, in profiles. Only keys that make sense to play with are listed
+const KEY_NAMES: [(&str, KeyCode); 50] = [
+    ("A", KeyCode::A), ("B", KeyCode::B), ("C", KeyCode::C), ("D", KeyCode::D),
+    ("E", KeyCode::E), ("F", KeyCode::F), ("G", KeyCode::G), ("H", KeyCode::H),
+    ("I", KeyCode::I), ("J", KeyCode::J), ("K", KeyCode::K), ("L", KeyCode::L),
+    ("M", KeyCode::M), ("N", KeyCode::N), ("O", KeyCode::O), ("P", KeyCode::P),
+    ("Q", KeyCode::Q), ("R", KeyCode::R), ("S", KeyCode::S), ("T", KeyCode::T),
+    ("U", KeyCode::U), ("V", KeyCode::V), ("W", KeyCode::W), ("X", KeyCode::X),
+    ("Y", KeyCode::Y), ("Z", KeyCode::Z),
+    ("0", KeyCode::Key0), ("1", KeyCode::Key1), ("2", KeyCode::Key2), ("3", KeyCode::Key3),
+    ("4", KeyCode::Key4), ("5", KeyCode::Key5), ("6", KeyCode::Key6), ("7", KeyCode::Key7),
+    ("8", KeyCode::Key8), ("9", KeyCode::Key9),
+    ("Up", KeyCode::Up), ("Down", KeyCode::Down), ("Left", KeyCode::Left), ("Right", KeyCode::Right),
+    ("Space", KeyCode::Space), ("LeftShift", KeyCode::LeftShift), ("RightShift", KeyCode::RightShift),
+    ("LeftControl", KeyCode::LeftControl), ("RightControl", KeyCode::RightControl),
+    ("/", KeyCode::Slash), (".", KeyCode::Period), (",", KeyCode::Comma), (";", KeyCode::Semicolon),
+    ("'", KeyCode::Apostrophe),
+];
+
+/// Looks up a key by the name it is saved under
+pub fn key_code(name: &str) -> Option<KeyCode> {
+    KEY_NAMES.iter().find(|(key_name, _)| *key_name == name).map(|(_, code)| *code)
+}
+
+/// The name a key is saved under, if it can be bound
+pub fn key_name(code: KeyCode) -> Option<&'static str> {
+    KEY_NAMES.iter().find(|(_, key_code)| *key_code == code).map(|(name, _)| *name)
+}
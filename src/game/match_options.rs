@@ -0,0 +1,212 @@
+//! This implements `MatchOptions`, which holds everything that is chosen before a match starts.
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::ai::Difficulty;
+use crate::game::ball::BallParams;
+use crate::game::court::CourtParams;
+use crate::game::team::TeamParams;
+use crate::Team;
+
+/// Who is in control of a team
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Controller {
+    Human,
+    Cpu(Difficulty),
+}
+
+impl Controller {
+    /// Cycles through human control and every AI difficulty
+    pub fn next(&self) -> Controller {
+        match self {
+            Controller::Human => Controller::Cpu(Difficulty::Easy),
+            Controller::Cpu(Difficulty::Easy) => Controller::Cpu(Difficulty::Normal),
+            Controller::Cpu(Difficulty::Normal) => Controller::Cpu(Difficulty::Hard),
+            Controller::Cpu(Difficulty::Hard) => Controller::Human,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Controller::Human => "Human".to_string(),
+            Controller::Cpu(difficulty) => format!("CPU ({})", difficulty.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchOptions {
+    pub team_one: Controller,
+    pub team_two: Controller,
+    /// The number of balls in play at the same time
+    #[serde(default = "MatchOptions::default_ball_count")]
+    pub ball_count: usize,
+    /// The id of the ball, from `balls.json`, that is played with
+    #[serde(default = "MatchOptions::default_ball")]
+    pub ball: String,
+    /// The id of the court, from `courts.json`, that is played on
+    #[serde(default = "MatchOptions::default_court")]
+    pub court: String,
+    /// The number of rounds a team has to win, to win the match
+    #[serde(default = "MatchOptions::default_rounds_to_win")]
+    pub rounds_to_win: u32,
+    /// The length of a round, in seconds. When it runs out, the team with more life left wins the
+    /// round. A round without a time limit has a length of 0
+    #[serde(default = "MatchOptions::default_round_time")]
+    pub round_time: u32,
+    /// The time, in seconds, that a team can hold on to a ball, before it has to give it up. A
+    /// shot clock of 0 turns it off
+    #[serde(default = "MatchOptions::default_shot_clock")]
+    pub shot_clock: u32,
+    /// The id of the team, from `teams.json`, that plays as team one. Without one, the default
+    /// colors are used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team_one_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team_two_id: Option<String>,
+    /// The ids of the characters picked for each roster slot of team one. Slots without a pick
+    /// will use the character of the team's roster, or the first character in
+    /// `player_characters.json`
+    #[serde(default)]
+    pub team_one_characters: Vec<Option<String>>,
+    /// The ids of the characters picked for each roster slot of team two
+    #[serde(default)]
+    pub team_two_characters: Vec<Option<String>>,
+}
+
+impl MatchOptions {
+    pub const MAX_BALL_COUNT: usize = 3;
+
+    pub fn team_id(&self, team: Team) -> Option<&str> {
+        match team {
+            Team::One => self.team_one_id.as_deref(),
+            Team::Two => self.team_two_id.as_deref(),
+        }
+    }
+
+    /// Picks the team that plays as `team`, which also fills its roster with the team's characters
+    pub fn set_team(&mut self, team: Team, params: Option<&TeamParams>) {
+        let characters = params
+            .map(|params| params.roster.iter().map(|slot| Some(slot.character.clone())).collect())
+            .unwrap_or_default();
+        let id = params.map(|params| params.id.clone());
+        match team {
+            Team::One => {
+                self.team_one_id = id;
+                self.team_one_characters = characters;
+            }
+            Team::Two => {
+                self.team_two_id = id;
+                self.team_two_characters = characters;
+            }
+        }
+    }
+
+    /// Returns the id of the character picked for a roster slot, if any
+    pub fn character(&self, team: Team, slot: usize) -> Option<&str> {
+        let characters = match team {
+            Team::One => &self.team_one_characters,
+            Team::Two => &self.team_two_characters,
+        };
+        characters.get(slot).and_then(Option::as_deref)
+    }
+
+    /// Picks a character for a roster slot
+    pub fn set_character(&mut self, team: Team, slot: usize, id: &str) {
+        let characters = match team {
+            Team::One => &mut self.team_one_characters,
+            Team::Two => &mut self.team_two_characters,
+        };
+        // the slots before it are left without a pick
+        if characters.len() <= slot {
+            characters.resize(slot + 1, None);
+        }
+        characters[slot] = Some(id.to_string());
+    }
+
+    const DEFAULT_BALL_COUNT: usize = 1;
+    const DEFAULT_BALL: &'static str = "soccer-ball";
+    const DEFAULT_COURT: &'static str = "schoolyard";
+    const DEFAULT_ROUNDS_TO_WIN: u32 = 2;
+    const DEFAULT_ROUND_TIME: u32 = 90;
+    const DEFAULT_SHOT_CLOCK: u32 = 10;
+    /// The round times that can be cycled through, with 0 standing for no time limit
+    const ROUND_TIMES: [u32; 5] = [30, 60, 90, 120, 0];
+
+    pub fn default_ball_count() -> usize {
+        Self::DEFAULT_BALL_COUNT
+    }
+
+    pub fn default_ball() -> String {
+        Self::DEFAULT_BALL.to_string()
+    }
+
+    pub fn default_court() -> String {
+        Self::DEFAULT_COURT.to_string()
+    }
+
+    /// Cycles through the courts in `courts`, in the order they are declared in
+    pub fn next_court(&self, courts: &[CourtParams]) -> String {
+        let index = courts.iter().position(|params| params.id == self.court);
+        let next = index.map_or(0, |index| (index + 1) % courts.len());
+        courts.get(next).map_or_else(Self::default_court, |params| params.id.clone())
+    }
+
+    pub fn default_rounds_to_win() -> u32 {
+        Self::DEFAULT_ROUNDS_TO_WIN
+    }
+
+    pub fn default_round_time() -> u32 {
+        Self::DEFAULT_ROUND_TIME
+    }
+
+    pub fn default_shot_clock() -> u32 {
+        Self::DEFAULT_SHOT_CLOCK
+    }
+
+    /// Cycles through the round times in `ROUND_TIMES`
+    pub fn next_round_time(&self) -> u32 {
+        let index = Self::ROUND_TIMES.iter().position(|&time| time == self.round_time);
+        let next = index.map_or(0, |index| (index + 1) % Self::ROUND_TIMES.len());
+        Self::ROUND_TIMES[next]
+    }
+
+    /// Cycles through the balls in `balls`, in the order they are declared in
+    pub fn next_ball(&self, balls: &[BallParams]) -> String {
+        let index = balls.iter().position(|params| params.id == self.ball);
+        let next = index.map_or(0, |index| (index + 1) % balls.len());
+        balls.get(next).map_or_else(Self::default_ball, |params| params.id.clone())
+    }
+
+    /// Cycles through every ball count, from one up to `MAX_BALL_COUNT`
+    pub fn next_ball_count(&self) -> usize {
+        self.ball_count % Self::MAX_BALL_COUNT + 1
+    }
+
+    pub fn controller(&self, team: Team) -> Controller {
+        match team {
+            Team::One => self.team_one,
+            Team::Two => self.team_two,
+        }
+    }
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        MatchOptions {
+            team_one: Controller::Human,
+            team_two: Controller::Cpu(Difficulty::Normal),
+            ball_count: Self::default_ball_count(),
+            ball: Self::default_ball(),
+            court: Self::default_court(),
+            rounds_to_win: Self::default_rounds_to_win(),
+            round_time: Self::default_round_time(),
+            shot_clock: Self::default_shot_clock(),
+            team_one_id: None,
+            team_two_id: None,
+            team_one_characters: Vec::new(),
+            team_two_characters: Vec::new(),
+        }
+    }
+}
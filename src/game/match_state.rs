@@ -0,0 +1,199 @@
+//! This implements `MatchState`, which holds what carries over between the rounds of a match.
+//! `Game` only lives for a single round, and is rebuilt when the next one starts.
+//!
+//! The timers of a round are kept in here as well. When the round timer runs out, the team with
+//! more life left wins the round, and if both teams have as much life left, the round goes into
+//! sudden death, where the next hit decides it. The shot clock keeps a team from holding on to a
+//! ball for too long, even when it passes the ball around.
+
+use std::collections::HashMap;
+
+use crate::game::ball::BallState;
+use crate::game::events::GameEvent;
+use crate::game::match_options::MatchOptions;
+use crate::game::stats::PlayerStats;
+use crate::game::{other_team, Game};
+use crate::Team;
+
+/// How a finished match went, as returned by `local_game`
+#[derive(Debug, Clone)]
+pub struct MatchResult {
+    pub winner: Team,
+    pub round_wins: HashMap<Team, u32>,
+    pub time_played: f32,
+    /// What each player did over the whole match
+    pub stats: Vec<PlayerStats>,
+}
+
+/// How `local_game` ended
+#[derive(Debug, Clone)]
+pub enum MatchEnd {
+    /// The match was played until a team won it
+    Finished(MatchResult),
+    /// The match was left from the pause menu, to go back to the main menu
+    QuitToMenu,
+}
+
+pub struct MatchState {
+    /// The current round, starting at 1
+    pub round: u32,
+    pub round_wins: HashMap<Team, u32>,
+    /// The first team to win this many rounds wins the match
+    pub rounds_to_win: u32,
+    /// The time, in seconds, that has been played, leaving out pauses and the breaks between rounds
+    pub time_played: f32,
+    /// The length of a round, in seconds, or `None` if rounds have no time limit
+    pub round_time: Option<f32>,
+    /// The time, in seconds, left in the current round
+    pub round_time_left: f32,
+    /// This is true once the round timer has run out with both teams on the same life
+    pub sudden_death: bool,
+    /// The time, in seconds, a team can hold on to a ball, or `None` if there is no shot clock
+    pub shot_clock: Option<f32>,
+    /// The team that has each ball, by index in `Game::balls`, and the time, in seconds, it has
+    /// had it for. Passing a ball between teammates keeps the clock running, so it only starts
+    /// over once the ball is thrown at the other team, or turned over
+    pub possessions: Vec<Option<(Team, f32)>>,
+    /// What each player did in the rounds that were finished. The stats of a round that is
+    /// started over are left out
+    pub stats: Vec<PlayerStats>,
+}
+
+impl MatchState {
+    pub fn new(options: &MatchOptions) -> Self {
+        let round_time = Some(options.round_time as f32).filter(|&time| time > 0.);
+        let shot_clock = Some(options.shot_clock as f32).filter(|&time| time > 0.);
+        MatchState {
+            round: 1,
+            round_wins: HashMap::new(),
+            rounds_to_win: options.rounds_to_win,
+            time_played: 0.,
+            round_time,
+            round_time_left: round_time.unwrap_or(0.),
+            sudden_death: false,
+            shot_clock,
+            possessions: Vec::new(),
+            stats: Vec::new(),
+        }
+    }
+
+    pub fn wins(&self, team: Team) -> u32 {
+        self.round_wins.get(&team).copied().unwrap_or(0)
+    }
+
+    /// Resets the timers, for when the current round is started over
+    pub fn restart_round(&mut self) {
+        self.round_time_left = self.round_time.unwrap_or(0.);
+        self.sudden_death = false;
+        self.possessions.clear();
+    }
+
+    /// Records the winner and the stats of the current round and moves on to the next one
+    pub fn end_round(&mut self, winner: Team, stats: &[PlayerStats]) {
+        *self.round_wins.entry(winner).or_insert(0) += 1;
+        if self.stats.is_empty() {
+            self.stats = stats.to_vec();
+        } else {
+            for (total, round) in self.stats.iter_mut().zip(stats) {
+                total.add(round);
+            }
+        }
+        self.round += 1;
+        self.restart_round();
+    }
+
+    /// The team that has won enough rounds to win the match, if any
+    pub fn winner(&self) -> Option<Team> {
+        [Team::One, Team::Two]
+            .into_iter()
+            .find(|&team| self.wins(team) >= self.rounds_to_win)
+    }
+
+    /// How the match went, once a team has won enough rounds
+    pub fn result(&self) -> Option<MatchResult> {
+        self.winner().map(|winner| MatchResult {
+            winner,
+            round_wins: self.round_wins.clone(),
+            time_played: self.time_played,
+            stats: self.stats.clone(),
+        })
+    }
+
+    /// The time, in seconds, left on the shot clock of a ball, if a team has it
+    pub fn shot_clock_left(&self, ball_index: usize) -> Option<f32> {
+        let (_, held_for) = self.possessions.get(ball_index).copied().flatten()?;
+        self.shot_clock.map(|shot_clock| (shot_clock - held_for).max(0.))
+    }
+
+    /// Runs the timers for a frame of play. `team_life` is the life each team had left before the
+    /// frame was simulated, which is how a hit in sudden death is noticed.
+    pub fn update(&mut self, game: &mut Game, team_life: [i32; 2], dt: f32) {
+        if game.winner.is_some() {
+            return;
+        }
+        self.time_played += dt;
+        if self.sudden_death {
+            for (team, life) in [Team::One, Team::Two].into_iter().zip(team_life) {
+                if game.team_life(team) < life {
+                    game.declare_winner(other_team(team));
+                    return;
+                }
+            }
+        } else if self.round_time.is_some() {
+            self.round_time_left -= dt;
+            if self.round_time_left <= 0. {
+                self.round_time_left = 0.;
+                let (one, two) = (game.team_life(Team::One), game.team_life(Team::Two));
+                if one > two {
+                    game.declare_winner(Team::One);
+                } else if two > one {
+                    game.declare_winner(Team::Two);
+                } else {
+                    self.sudden_death = true;
+                }
+            }
+        }
+        self.update_shot_clocks(game, dt);
+    }
+
+    /// Runs the shot clock of every ball. This looks at the events of the frame, so it has to run
+    /// before they are dispatched
+    fn update_shot_clocks(&mut self, game: &mut Game, dt: f32) {
+        let shot_clock = match self.shot_clock {
+            Some(shot_clock) => shot_clock,
+            None => return,
+        };
+        self.possessions.resize(game.balls.len(), None);
+        for event in &game.events {
+            if let GameEvent::BallThrown { ball, .. } = *event {
+                self.possessions[ball] = None;
+            }
+        }
+        for ball_index in 0..game.balls.len() {
+            // nobody has the ball while it is loose, so whoever picks it up gets a full clock
+            if game.balls[ball_index].is_loose() {
+                self.possessions[ball_index] = None;
+                continue;
+            }
+            let holder = match game.balls[ball_index].state {
+                BallState::OnPlayersHand(holder) => Some(holder),
+                _ => None,
+            };
+            if let Some(holder) = holder {
+                let team = game.team_of(holder);
+                // the clock only starts over when the ball ends up with the other team
+                if !matches!(self.possessions[ball_index], Some((owner, _)) if owner == team) {
+                    self.possessions[ball_index] = Some((team, 0.));
+                }
+            }
+            if let Some((_, held_for)) = &mut self.possessions[ball_index] {
+                *held_for += dt;
+                // a ball that runs out of time during a pass is turned over once it is caught
+                if *held_for >= shot_clock && holder.is_some() {
+                    game.turn_over_ball(ball_index);
+                    self.possessions[ball_index] = None;
+                }
+            }
+        }
+    }
+}
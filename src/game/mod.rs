@@ -1,12 +1,16 @@
 use macroquad::prelude::*;
 use macroquad::prelude::animation::{Animation, AnimatedSprite};
 use std::collections::HashMap;
+use std::ops::Range;
+use macroquad::prelude::collections::storage;
 use macroquad::ui::Drag::No;
 use macroquad_platformer::World;
-use crate::{_x, _y, AnimationPlayer, BallState, colliding_with, DEFAULT_ZOOM, FacingTo, KeyCode, Player, PLAYER_HEIGHT, PLAYER_WIDTH, PlayerAction, PlayerState, Team, valid_position};
+use crate::{_x, _y, AnimationPlayer, BallState, DEFAULT_ZOOM, FacingTo, KeyCode, Player, PLAYER_HEIGHT, PLAYER_WIDTH, PlayerAction, PlayerState, Team};
 use crate::game::ball::animations::BallAnimationParams;
+use crate::math::{circle_rect_contact, rotate_vector, sweep_circle_rect, SweepHit};
 
 pub(crate) mod camera;
+pub(crate) mod ai;
 pub(crate) mod ball;
 pub(crate) mod field;
 pub mod player;
@@ -15,9 +19,41 @@ pub mod draw_utilities;
 pub mod animations;
 pub mod resources;
 pub mod character;
+pub mod settings;
+pub mod particles;
+pub mod input;
+pub mod match_options;
+pub mod character_select;
+pub mod team;
+pub mod team_builder;
+pub mod scene;
+pub mod match_state;
+pub mod pause_menu;
+pub mod hud;
+pub mod tournament;
+pub mod court;
+pub mod campaign;
+pub mod profile;
+pub mod stats;
+pub mod events;
+pub mod audio;
+pub mod hot_reload;
+pub mod validate_assets;
 
+use crate::game::ai::AiController;
+use crate::game::audio::Audio;
 use crate::game::ball::Ball;
+use crate::game::camera::GameCamera;
+use crate::game::court::CourtParams;
+use crate::game::events::{GameEvent, GameEventListener, HitZone};
 use crate::game::field::Field;
+use crate::game::input::PlayerInput;
+use crate::game::particles::Particles;
+use crate::game::settings::Settings;
+use crate::game::stats::PlayerStats;
+use crate::game::resources::Resources;
+use crate::game::team::TeamParams;
+use crate::error::{Error, ErrorKind, Result};
 
 #[derive(Eq, PartialEq)]
 pub enum Sideline {
@@ -41,8 +77,9 @@ pub enum GameState {
 
 pub struct Game {
     pub(crate) players: Vec<Player>,
-    pub(crate) balls: Vec<AnimationPlayer>,
-    pub(crate) ball: Ball,
+    pub(crate) ball_animations: Vec<AnimationPlayer>,
+    /// Every ball in play. Each one has its own state and is held by at most one player
+    pub(crate) balls: Vec<Ball>,
     pub(crate) team_with_ball: Team,
     pub(crate) field: Field,
     pub(crate) key_sets: HashMap<Team, HashMap<PlayerAction, KeyCode>>,
@@ -51,197 +88,571 @@ pub struct Game {
     pub(crate) textures: Vec<Texture2D>,
     pub(crate) zoom: Vec2,
     pub(crate) time_passed: f64,
-    pub(crate) gravity_line: f32,
     pub(crate) world: World,
+    pub(crate) camera: GameCamera,
+    /// Frames left of the freeze that follows a hard hit
+    pub(crate) hit_stop: u32,
+    pub(crate) particles: Particles,
+    pub(crate) winner: Option<Team>,
+    /// The AI controlling each team. Teams that are not in here are controlled by a key set
+    pub(crate) ai: HashMap<Team, AiController>,
+    /// The player each human team took control of with the switch key. It stays in control until
+    /// a player of its team gets a ball
+    pub(crate) selected_players: HashMap<Team, usize>,
+    /// The team from `teams.json` playing each side, if one was picked
+    pub(crate) teams: HashMap<Team, TeamParams>,
+    /// The court the match is played on
+    pub(crate) court: CourtParams,
+    /// The id of the ball from `balls.json` that the balls are made from
+    pub(crate) ball_id: String,
+    /// What each player did this round, by index in `players`
+    pub(crate) stats: Vec<PlayerStats>,
+    /// What happened during the last frame of play, which is handed to the listeners by
+    /// `dispatch_events`
+    pub(crate) events: Vec<GameEvent>,
 }
 
 impl Game {
-    pub(crate) fn update_ball_state(&mut self) {
-        match self.ball.state {
+    /// How far from the mid line a ball is put down, when it is turned over by the shot clock
+    const TURNOVER_DISTANCE: f32 = 100.;
+    /// Edges that a ball crosses within this fraction of a frame of each other are crossed at once
+    const SIMULTANEOUS_CROSSING: f32 = 0.01;
+    /// A player's actor in `world` that is further than this from their collider is moved back to it
+    const ACTOR_TOLERANCE: f32 = 0.01;
+
+    pub(crate) fn update_ball_state(&mut self, ball_index: usize) {
+        let ball = &mut self.balls[ball_index];
+        let animation = &mut self.ball_animations[ball.animation];
+        match ball.state {
             BallState::OnGround => {
                 //self.set_zoom(None);
-                self.balls[self.ball.animation].set_animation(Ball::IDLE_ANIMATION_ID);
+                animation.set_animation(Ball::IDLE_ANIMATION_ID);
             }
             BallState::OnAir(facing_to) => {
                 // check if hitting any players
-                self.balls[self.ball.animation].set_animation(Ball::MOVE_ANIMATION_ID);
+                animation.set_animation(Ball::MOVE_ANIMATION_ID);
+                if ball.super_shot {
+                    self.particles.spawn(Particles::SUPER_SHOT_TRAIL_EFFECT_ID, ball.pos);
+                }
             }
             BallState::OnPlayersHand(player_index) => {
-                self.balls[self.ball.animation].set_animation(Ball::IDLE_ANIMATION_ID);
+                animation.set_animation(Ball::IDLE_ANIMATION_ID);
+                self.attach_ball_to_player(ball_index, player_index);
+            }
+            BallState::AfterHittingPlayer { normal, time_passed } => {
+                animation.set_animation(Ball::MOVE_ANIMATION_ID);
+                ball.gravity_line = ball.pos.y + PLAYER_HEIGHT;
+                ball.after_collision(normal, time_passed)
             }
-            BallState::AfterHittingPlayer { change_x, change_y, time_passed } => {
-                self.balls[self.ball.animation].set_animation(Ball::MOVE_ANIMATION_ID);
-                self.gravity_line = self.ball.pos.y + PLAYER_HEIGHT;
-                self.ball.after_collision(change_x, change_y, time_passed)
+            BallState::AfterHittingBoundary { normal, time_passed } => {
+                animation.set_animation(Ball::MOVE_ANIMATION_ID);
+                ball.gravity_line = ball.pos.y + PLAYER_HEIGHT;
+                ball.after_collision(normal, time_passed)
             }
-            BallState::AfterHittingBoundary { time_passed } => {
-                self.balls[self.ball.animation].set_animation(Ball::MOVE_ANIMATION_ID);
-                self.gravity_line = self.ball.pos.y + PLAYER_HEIGHT;
-                self.ball.after_collision(true, true, time_passed)
+            BallState::AfterHittingBall { time_passed } => {
+                animation.set_animation(Ball::MOVE_ANIMATION_ID);
+                ball.gravity_line = ball.pos.y + PLAYER_HEIGHT;
+                // the velocity was already changed by the collision
+                ball.after_collision(Vec2::ZERO, time_passed)
             }
             BallState::Stopping => {
-                self.balls[self.ball.animation].set_animation(Ball::IDLE_ANIMATION_ID);
-                self.ball.stop()
+                animation.set_animation(Ball::IDLE_ANIMATION_ID);
+                ball.stop()
             }
             BallState::BallFalling { time_passed } => {
-                self.balls[self.ball.animation].set_animation(Ball::MOVE_ANIMATION_ID);
-                self.ball.ball_falling(time_passed, self.gravity, self.gravity_line)
+                animation.set_animation(Ball::MOVE_ANIMATION_ID);
+                ball.ball_falling(time_passed, self.gravity)
             }
         }
     }
 
-    pub fn attach_ball_to_player(&mut self, player_index: usize) {
-        let team_with_ball = self.which_team_has_ball();
-        match team_with_ball {
-            Team::One => {
-                self.ball.pos = self.players[player_index].pos + Vec2::new(PLAYER_WIDTH, 0.);
-            }
-            Team::Two => {
-                self.ball.pos = self.players[player_index].pos - Vec2::new(self.ball.r + 10., 0.);
-            }
+    /// Returns the index of the ball the player is holding, if any
+    pub(crate) fn ball_held_by(&self, player_index: usize) -> Option<usize> {
+        self.balls.iter().position(|ball| ball.state == BallState::OnPlayersHand(player_index))
+    }
+
+    pub fn attach_ball_to_player(&mut self, ball_index: usize, player_index: usize) {
+        let ball = &mut self.balls[ball_index];
+        // the ball is centered on the weapon mount of the player
+        ball.pos = self.players[player_index].weapon_mount_position();
+    }
+
+    /// Bounces the ball off the edges of the field. A ball that went past an edge during the frame
+    /// is moved back to where it crossed the first one, so that a fast ball does not bounce from
+    /// far outside of the field
+    pub fn is_ball_hitting_boundary(&mut self, ball_index: usize) {
+        let ball = &mut self.balls[ball_index];
+        if let BallState::OnPlayersHand(_) = ball.state {
+            return;
+        }
+        // a live ball may fly past the back lines, so that it can reach the outfielders
+        let margin = if ball.is_live() { Field::OUTFIELD_DEPTH + PLAYER_WIDTH } else { 0. };
+        let top = self.field.top_edge + 10. - ball.r;
+        let bottom = self.field.bottom_edge - 10. - ball.r;
+        let left = self.field.left_edge + 10. - margin - ball.r;
+        let right = self.field.right_edge - 10. + margin - ball.r;
+        // check if hitting the borders, only bouncing balls that are moving away from the court.
+        // Every edge that was crossed has the time it was crossed at, and a normal that points
+        // back into the field
+        let (prev, pos, vel) = (ball.prev_pos, ball.pos, ball.vel);
+        let mut crossed: Vec<(f32, Vec2)> = Vec::new();
+        if pos.y < top && vel.y < 0. {
+            crossed.push((crossing_time(prev.y, pos.y, top), Vec2::new(0., 1.)));
+        } else if pos.y > bottom && vel.y > 0. {
+            crossed.push((crossing_time(prev.y, pos.y, bottom), Vec2::new(0., -1.)));
         }
+        if pos.x > right && vel.x > 0. {
+            crossed.push((crossing_time(prev.x, pos.x, right), Vec2::new(-1., 0.)));
+        } else if pos.x < left && vel.x < 0. {
+            crossed.push((crossing_time(prev.x, pos.x, left), Vec2::new(1., 0.)));
+        }
+        let time = match crossed.iter().map(|&(time, _)| time).reduce(f32::min) {
+            Some(time) => time,
+            None => return,
+        };
+        // crossing two edges at once, through a corner, bounces the ball off both
+        let normal: Vec2 = crossed.iter()
+            .filter(|&&(crossed_at, _)| crossed_at - time < Self::SIMULTANEOUS_CROSSING)
+            .map(|(_, normal)| normal)
+            .sum();
+        ball.pos = prev + (pos - prev) * time;
+        ball.outside_edge(self.time_passed, normal.normalize());
+        self.events.push(GameEvent::BallOutOfBounds { ball: ball_index, pos: ball.pos });
     }
 
-    pub fn is_ball_hitting_boundary(&mut self) {
-        // check if hitting the borders
-        let b_pos = &self.ball.pos;
-        let outside_top_or_bottom_edge = b_pos.y + self.ball.r < self.field.top_edge + 10. || b_pos.y + self.ball.r > self.field.bottom_edge - 10.;
-        let outside_left_or_right_edge = b_pos.x + self.ball.r > self.field.right_edge - 10. || b_pos.x + self.ball.r < self.field.left_edge + 10.;
-        if outside_left_or_right_edge || outside_top_or_bottom_edge {
-            self.ball.outside_edge(self.time_passed, outside_left_or_right_edge, outside_top_or_bottom_edge);
+    /// Bounces balls that run into each other off one another, depending on their mass. Held balls are left alone, and a
+    /// live ball that is knocked off its course stops being live.
+    pub fn is_ball_hitting_other_balls(&mut self) {
+        for a in 0..self.balls.len() {
+            for b in a + 1..self.balls.len() {
+                let (left, right) = self.balls.split_at_mut(b);
+                let (ball_a, ball_b) = (&mut left[a], &mut right[0]);
+                if ball_a.is_held() || ball_b.is_held() {
+                    continue;
+                }
+                let delta = ball_b.pos - ball_a.pos;
+                let distance = delta.length();
+                let min_distance = ball_a.r + ball_b.r;
+                if distance >= min_distance || distance == 0. {
+                    continue;
+                }
+                let normal = delta / distance;
+                let approach_speed = (ball_a.vel - ball_b.vel).dot(normal);
+                if approach_speed <= 0. {
+                    // they are already moving apart
+                    continue;
+                }
+                // the push along the line between them is shared out by mass, so a light ball
+                // bounces off a heavy one, and bounciness decides how much speed is kept
+                let inverse_mass_a = 1. / ball_a.physics.mass;
+                let inverse_mass_b = 1. / ball_b.physics.mass;
+                let restitution = ball_a.physics.bounciness.min(ball_b.physics.bounciness);
+                let impulse = (1. + restitution) * approach_speed / (inverse_mass_a + inverse_mass_b);
+                ball_a.vel -= normal * impulse * inverse_mass_a;
+                ball_b.vel += normal * impulse * inverse_mass_b;
+                let overlap = (min_distance - distance) / (inverse_mass_a + inverse_mass_b);
+                ball_a.pos -= normal * overlap * inverse_mass_a;
+                ball_b.pos += normal * overlap * inverse_mass_b;
+                ball_a.state = BallState::AfterHittingBall { time_passed: self.time_passed };
+                ball_b.state = BallState::AfterHittingBall { time_passed: self.time_passed };
+            }
         }
     }
 
-    pub fn is_the_ball_hitting_any_player(&mut self) {
-        for i in 0..self.players.len() {
+    /// Checks the ball against every player. The ball is swept from where it was at the start of
+    /// the frame, so that a fast ball can not pass through a player, and players are handled in
+    /// the order the ball reached them, as it flies over ducking players
+    pub fn is_the_ball_hitting_any_player(&mut self, ball_index: usize) {
+        if self.balls[ball_index].is_held() {
+            return;
+        }
+        let mut hits: Vec<(usize, SweepHit)> = {
+            let ball = &self.balls[ball_index];
+            (0..self.players.len())
+                .filter(|&i| self.players[i].life > 0)
+                .filter_map(|i| {
+                    sweep_circle_rect(ball.prev_pos, ball.pos, ball.r, self.players[i].collider())
+                        .map(|hit| (i, hit))
+                })
+                .collect()
+        };
+        hits.sort_by(|(_, a), (_, b)| a.time.partial_cmp(&b.time).unwrap());
+        for (i, hit) in hits {
+            // players can only hold one ball at a time
+            let can_catch = self.ball_held_by(i).is_none();
+            let thrown_by = self.balls[ball_index].thrown_by;
+            let thrower_power = thrown_by.map_or(1., |thrower| self.players[thrower].power);
+            let is_live = self.balls[ball_index].is_live();
+            if is_live && thrown_by == Some(i) {
+                // the ball is still leaving the hand of the thrower
+                continue;
+            }
+            let thrower_team = thrown_by.map(|thrower| self.team_of(thrower));
+            let is_thrown_by_teammate = is_live && thrower_team == Some(self.team_of(i));
+            let is_hit_by_opponent = is_live && !is_thrown_by_teammate;
+            let ball = &mut self.balls[ball_index];
             let player: &mut Player = &mut self.players[i];
-            let (collided, change_x, change_y) = colliding_with(
-                &self.ball.pos, self.ball.r, &player.pos,
-                &Vec2::new(
-                    PLAYER_WIDTH - self.ball.r * 2.,
-                    PLAYER_HEIGHT - self.ball.r * 2.)
-            );
-            if collided && player.state == PlayerState::Catching {
-                self.ball.picked_up(i);
-            } else if collided {
+            let collider = player.collider();
+            // where the ball was when it reached the player
+            let contact_pos = ball.prev_pos + (ball.pos - ball.prev_pos) * hit.time;
+            if player.state == PlayerState::Ducking {
+                // the ball flies over a ducking player, which counts as a dodge once per throw
+                if is_hit_by_opponent && !ball.dodged_by.contains(&i) {
+                    ball.dodged_by.push(i);
+                    self.events.push(GameEvent::BallDodged { ball: ball_index, player: i });
+                }
+                continue;
+            }
+            if can_catch && (player.state == PlayerState::Catching || is_thrown_by_teammate) {
+                self.events.push(if is_live {
+                    GameEvent::BallCaught { ball: ball_index, player: i, from_opponent: is_hit_by_opponent, pos: contact_pos }
+                } else {
+                    GameEvent::BallPickedUp { ball: ball_index, player: i }
+                });
+                ball.picked_up(i);
+                return;
+            } else if is_live {
+                // the player was trying to catch the ball, but could not hold on to it
+                if player.state == PlayerState::Catching || is_thrown_by_teammate {
+                    self.events.push(GameEvent::BallFumbled { ball: ball_index, player: i });
+                }
                 player.state = PlayerState::Hurting;
-                self.ball.state = BallState::AfterHittingPlayer { time_passed: self.time_passed, change_x, change_y };
+                // outfielders can not be knocked out
+                if is_hit_by_opponent && !player.is_outfielder {
+                    let damage = (ball.damage() as f32 * thrower_power).round() as i32;
+                    player.life -= damage;
+                    self.events.push(GameEvent::PlayerHit {
+                        ball: ball_index,
+                        player: i,
+                        thrower: thrown_by,
+                        zone: HitZone::at(contact_pos.y, collider),
+                        damage,
+                        pos: contact_pos,
+                        velocity: ball.vel,
+                        max_speed: ball.physics.max_speed,
+                    });
+                    if player.life <= 0 {
+                        self.events.push(GameEvent::PlayerEliminated { player: i, by: thrown_by });
+                    }
+                }
+                // the ball is moved back to where it reached the player, out of them if it was
+                // already inside at the start of the frame, and bounces off them
+                ball.pos = contact_pos;
+                if let Some(contact) = circle_rect_contact(ball.pos, ball.r, collider) {
+                    ball.pos += contact.normal * contact.penetration;
+                }
+                ball.state = BallState::AfterHittingPlayer { normal: hit.normal, time_passed: self.time_passed };
+                return;
             }
         }
     }
 
+    /// Hands the events of the last frame to every listener. This is done once the frame is over,
+    /// so that listeners see everything that happened in it, including a round being won by the
+    /// timers of `MatchState`
+    pub fn dispatch_events(&mut self) {
+        let events = std::mem::take(&mut self.events);
+        // there is no audio in storage when running without a window
+        let mut audio = storage::try_get_mut::<Audio>();
+        for event in &events {
+            self.particles.on_event(event);
+            self.stats.on_event(event);
+            if let Some(audio) = &mut audio {
+                audio.on_event(event);
+            }
+            self.on_event(event);
+        }
+        self.events = events;
+    }
+
+    /// Shakes the camera in the direction of the hit and freezes the game for a few frames.
+    /// Both effects scale with the speed of the ball, compared to the fastest it can fly, and can
+    /// be turned off in `Settings`.
+    fn impact_feedback(&mut self, velocity: Vec2, max_speed: f32) {
+        let settings = storage::get::<Settings>();
+        let strength = (velocity.length() / (max_speed * Ball::SUPER_SHOT_SPEED_FACTOR)).min(1.);
+        if settings.screen_shake && strength > 0. {
+            let direction = velocity.normalize();
+            let magnitude = Self::HIT_SHAKE_MAGNITUDE * strength * settings.screen_shake_scale;
+            self.camera.shake_noise_dir(magnitude, Self::HIT_SHAKE_FRAMES, 1.0, (direction.x, direction.y));
+            self.camera.shake_rotational(magnitude / 2., Self::HIT_SHAKE_FRAMES);
+        }
+        if settings.hit_stop {
+            self.hit_stop = (settings.hit_stop_frames as f32 * strength).round() as u32;
+        }
+    }
+
     pub fn update_player(&mut self, player_index: usize) {
-        let current_team = if player_index >= self.players.len() / 2 { Team::Two } else { Team::One };
+        if self.players[player_index].life <= 0 {
+            let player = &mut self.players[player_index];
+            player.state = PlayerState::Died;
+            player.set_animation();
+            return;
+        }
+        self.stats[player_index].time_alive += get_frame_time();
+        let current_team = self.team_of(player_index);
         let active_player = self.get_active_player_for_team(current_team);
-        if Some(player_index) != active_player { return; }
-        let target_pos = self.find_target_pos(&current_team);
-        let keys = self.key_sets.get(&current_team).unwrap();
-        let keys_pressed = [
-            is_key_down(keys[&PlayerAction::MoveUp]),
-            is_key_down(keys[&PlayerAction::MoveRight]),
-            is_key_down(keys[&PlayerAction::MoveDown]),
-            is_key_down(keys[&PlayerAction::MoveLeft]),
-            is_key_down(keys[&PlayerAction::B]),
-            is_key_down(keys[&PlayerAction::A])
-        ];
+        // control follows the ball, so the teammates are left to the AI until they get near it, or
+        // are picked with the switch key
+        let input = if Some(player_index) == active_player {
+            self.read_input(player_index)
+        } else {
+            ai::support(self, player_index)
+        };
+        self.apply_input(player_index, input);
+    }
+
+    /// Hands control of each human team to its next teammate in play, if its switch key was
+    /// pressed. This runs before the players are updated, so that a switch is only read once
+    pub fn update_selected_players(&mut self) {
+        for team in [Team::One, Team::Two] {
+            // control follows the ball again, once the team has one
+            if self.team_range(team).any(|i| self.ball_held_by(i).is_some()) {
+                self.selected_players.remove(&team);
+            }
+            let is_switching = !self.ai.contains_key(&team)
+                && self.key_sets.get(&team).is_some_and(|keys| PlayerInput::from_keys(keys).switch);
+            if is_switching {
+                self.switch_player(team);
+            }
+        }
+    }
+
+    /// Selects the teammate in play after the active player of `team`, in roster order
+    pub(crate) fn switch_player(&mut self, team: Team) {
+        let range = self.team_range(team);
+        let current = self.get_active_player_for_team(team).unwrap_or(range.start);
+        let next = (1..range.len())
+            .map(|step| range.start + (current - range.start + step) % range.len())
+            .find(|&i| self.players[i].life > 0);
+        if let Some(next) = next {
+            self.selected_players.insert(team, next);
+        }
+    }
+
+    /// Asks whoever controls the team of the player, the AI or a key set, for this frame's input
+    fn read_input(&mut self, player_index: usize) -> PlayerInput {
+        let current_team = self.team_of(player_index);
+        if let Some(mut ai) = self.ai.remove(&current_team) {
+            let input = ai.think(self, player_index);
+            self.ai.insert(current_team, ai);
+            input
+        } else {
+            PlayerInput::from_keys(&self.key_sets[&current_team])
+        }
+    }
+
+    fn apply_input(&mut self, player_index: usize, input: PlayerInput) {
+        let current_team = self.team_of(player_index);
+        // the players in play can not walk into each other
+        let others: Vec<Rect> = (0..self.players.len())
+            .filter(|&i| i != player_index && self.players[i].life > 0)
+            .map(|i| self.players[i].collider())
+            .collect();
+        let held_ball = self.ball_held_by(player_index);
+        let throw_direction = held_ball.and_then(|ball_index| input.target
+            .or_else(|| self.find_target(current_team))
+            .map(|target| rotate_vector(self.direction_to(ball_index, target), input.aim_offset)));
+        let pass_direction = held_ball.and_then(|ball_index| input.target
+            .or_else(|| self.find_pass_target(player_index))
+            .map(|target| self.direction_to(ball_index, target)));
         {
             let player: &mut Player = &mut self.players[player_index];
-            if player.life <= 0 {
-                player.state = PlayerState::Died;
-                return;
-            }
-            if keys_pressed[4] {
-                if self.ball.state == BallState::OnPlayersHand(player_index) {
-                    self.ball.throwing(target_pos, self.ball.pos, player.facing_to.clone());
-                    player.state = PlayerState::Throwing;
+            if input.b {
+                if let Some(ball_index) = held_ball {
+                    if let Some(direction) = throw_direction {
+                        let ball = &mut self.balls[ball_index];
+                        // throwing at the end of a run-up makes it a super shot
+                        if player.state == PlayerState::Running {
+                            ball.super_throwing(direction, ball.pos, player.facing_to);
+                        } else {
+                            ball.throwing(direction, ball.pos, player.facing_to);
+                        }
+                        self.events.push(GameEvent::BallThrown { ball: ball_index, thrower: player_index, super_shot: ball.super_shot });
+                        player.state = PlayerState::Throwing;
+                        player.run_time = 0.;
+                    }
                 } else {
                     let (facing_to, player_action) = FacingTo::opposite_direction(player.facing_to);
-                    let key_code = keys.get(&player_action).unwrap();
-                    if self.ball.state == BallState::OnAir(facing_to) && is_key_down(*key_code){
+                    let is_ball_incoming = self.balls.iter().any(|ball| ball.state == BallState::OnAir(facing_to));
+                    if is_ball_incoming && input.is_pressed(&player_action) {
                         player.state = PlayerState::Catching;
                         // code to handle catching of ball
-                    } else if self.ball.state == BallState::OnGround {
+                    } else if self.balls.iter().any(|ball| ball.state == BallState::OnGround) {
                         player.state = PlayerState::Catching;
                     }
                 }
-            } else if keys_pressed[5] {
-                player.state = PlayerState::Ducking;
+            } else if input.a {
+                if let Some(ball_index) = held_ball {
+                    if let Some(direction) = pass_direction {
+                        let ball = &mut self.balls[ball_index];
+                        ball.throwing(direction, ball.pos, player.facing_to);
+                        self.events.push(GameEvent::BallPassed { ball: ball_index, passer: player_index });
+                        player.state = PlayerState::Passing;
+                        player.run_time = 0.;
+                    }
+                } else {
+                    player.state = PlayerState::Ducking;
+                }
             } else {
-                let (rotation, facing_to, acc) = calculate_movement([keys_pressed[0], keys_pressed[1], keys_pressed[2], keys_pressed[3]]);
-                if acc.is_some() {
+                let (rotation, facing_to, acc) = calculate_movement(input.movement());
+                if let Some(acc) = acc {
                     player.facing_to = facing_to;
                     if player.facing_to == FacingTo::FacingRight || player.facing_to == FacingTo::FacingLeft {
-                        player.facing_to_before = player.facing_to.clone();
+                        player.facing_to_before = player.facing_to;
                     }
                     player.rotation = rotation;
-                    player.vel += acc.unwrap_or(-player.vel);
-                    if player.vel.length() > 0. {
+                    player.run_time += get_frame_time();
+                    let walk_speed = player.move_speed * get_frame_time();
+                    let max_speed = if player.run_time >= Player::RUN_UP_TIME {
+                        player.state = PlayerState::Running;
+                        walk_speed * Player::RUN_SPEED_FACTOR
+                    } else {
                         player.state = PlayerState::Walking;
-                    }
-                    if player.vel.length() > 5. {
-                        player.vel = player.vel.normalize() * 5.;
+                        walk_speed
+                    };
+                    player.vel += acc;
+                    if player.vel.length() > max_speed {
+                        player.vel = player.vel.normalize() * max_speed;
                     }
                     let prev_pos = player.pos;
-                    player.pos += player.vel;
-                    if !valid_position(&player.pos) {
-                        player.state = PlayerState::Idle;
+                    let prev_collider = player.collider();
+                    if let Some(actor) = player.actor {
+                        // the actor is the collider, which moves relative to the sprite when the
+                        // player turns around, and is put back in place if the player was moved
+                        // by something else
+                        let origin = player.collider_origin();
+                        if (self.world.actor_pos(actor) - prev_collider.point()).length() > Self::ACTOR_TOLERANCE {
+                            self.world.set_actor_position(actor, prev_collider.point());
+                        }
+                        // walls and obstacles stop the player along one axis, so they slide along them
+                        if !self.world.move_h(actor, player.vel.x) {
+                            player.vel.x = 0.;
+                        }
+                        if !self.world.move_v(actor, player.vel.y) {
+                            player.vel.y = 0.;
+                        }
+                        player.pos = self.world.actor_pos(actor) - origin;
+                    } else {
+                        player.pos += player.vel;
+                    }
+                    // players who already overlap can still walk apart
+                    let collider = player.collider();
+                    let bumped = others.iter()
+                        .any(|other| other.overlaps(&collider) && !other.overlaps(&prev_collider));
+                    if bumped {
                         player.pos = prev_pos;
+                        if let Some(actor) = player.actor {
+                            self.world.set_actor_position(actor, prev_collider.point());
+                        }
+                    }
+                    if bumped || player.vel == Vec2::ZERO {
+                        player.state = PlayerState::Idle;
                         player.vel = Vec2::ZERO;
+                        player.run_time = 0.;
+                    } else if self.time_passed - player.dust_time > Player::DUST_INTERVAL {
+                        player.dust_time = self.time_passed;
+                        let feet = player.pos + Vec2::new(PLAYER_WIDTH / 2., PLAYER_HEIGHT);
+                        self.events.push(GameEvent::Footstep { player: player_index, pos: feet });
                     }
                 } else {
                     player.state = PlayerState::Idle;
                     player.vel = Vec2::ZERO;
+                    player.run_time = 0.;
                 }
             }
             player.set_animation();
         }
     }
 
-    fn find_target_pos(&self, &current_team: &Team) -> Vec2 {
-        let other_team = other_team(current_team);
-        let target_player_index = self.get_active_player_for_team(other_team).unwrap();
-        let pos = self.ball.pos;
-        let target = self.players[target_player_index].pos;
-        (target - pos).normalize()
+    fn direction_to(&self, ball_index: usize, player_index: usize) -> Vec2 {
+        (self.players[player_index].pos - self.balls[ball_index].pos).normalize()
+    }
+
+    /// Human players throw at the active player of the other team
+    fn find_target(&self, current_team: Team) -> Option<usize> {
+        self.get_active_player_for_team(other_team(current_team))
+    }
+
+    /// Human players pass to their outfielder, or to their nearest teammate if they are the
+    /// outfielder
+    fn find_pass_target(&self, player_index: usize) -> Option<usize> {
+        let position = self.players[player_index].pos;
+        let teammates = self.team_range(self.team_of(player_index))
+            .filter(|&i| i != player_index && self.players[i].life > 0);
+        let outfielder = teammates.clone().find(|&i| self.players[i].is_outfielder);
+        outfielder.or_else(|| teammates.min_by(|&a, &b| {
+            let distance_a = self.players[a].pos.distance(position);
+            let distance_b = self.players[b].pos.distance(position);
+            distance_a.partial_cmp(&distance_b).unwrap()
+        }))
     }
 }
 
 
 impl Game {
+    const HIT_SHAKE_MAGNITUDE: f32 = 0.4;
+    const HIT_SHAKE_FRAMES: i32 = 10;
+
+    /// The first half of `players` is team one and the second half is team two
+    pub(crate) fn team_of(&self, player_index: usize) -> Team {
+        if player_index >= self.players.len() / 2 { Team::Two } else { Team::One }
+    }
+
+    /// The indices in `players` of the given team's players
+    pub(crate) fn team_range(&self, which_team: Team) -> Range<usize> {
+        match which_team {
+            Team::One => 0..self.players.len() / 2,
+            Team::Two => self.players.len() / 2..self.players.len(),
+        }
+    }
+
+    /// A player that is holding a ball is the active player of its team. If nobody is, it is the
+    /// player picked with the switch key, or else the player that is nearest to any of the balls.
     pub(crate) fn get_active_player_for_team(&self, which_team: Team) -> Option<usize> {
-        let team_one = (0, self.players.len() / 2);
-        let team_two = (self.players.len() / 2, self.players.len());
-        let target_team_start_index = match which_team {
-            Team::One => team_one,
-            Team::Two => team_two
-        };
+        let holder = self.team_range(which_team)
+            .find(|&i| self.players[i].life > 0 && self.ball_held_by(i).is_some());
+        if holder.is_some() {
+            return holder;
+        }
+        let selected = self.selected_players.get(&which_team).copied()
+            .filter(|&i| self.players[i].life > 0);
+        if selected.is_some() {
+            return selected;
+        }
         let mut distance = 9999.;
         let mut which_player = None;
-        for i in target_team_start_index.0..target_team_start_index.1 {
+        for i in self.team_range(which_team) {
             let player: &Player = &self.players[i];
-            if (self.ball.pos - player.pos).length() < distance {
-                distance = (self.ball.pos - player.pos).length();
-                which_player = Some(i);
+            if player.life <= 0 {
+                continue;
+            }
+            for ball in &self.balls {
+                if (ball.pos - player.pos).length() < distance {
+                    distance = (ball.pos - player.pos).length();
+                    which_player = Some(i);
+                }
             }
         }
         which_player
     }
 
-    pub(crate) fn is_on_sideline(&self) -> Sideline {
-        if self.ball.pos.y < self.field.top_edge {
+    pub(crate) fn is_on_sideline(&self, ball_index: usize) -> Sideline {
+        let ball = &self.balls[ball_index];
+        if ball.pos.y < self.field.top_edge {
             Sideline::Top
-        } else if self.ball.pos.y > self.field.bottom_edge {
+        } else if ball.pos.y > self.field.bottom_edge {
             Sideline::Bottom
-        } else if self.ball.pos.x > self.field.right_edge || self.ball.pos.x < self.field.left_edge {
+        } else if ball.pos.x > self.field.right_edge || ball.pos.x < self.field.left_edge {
             Sideline::Back
         } else {
             Sideline::Inside
         }
     }
 
-    pub fn which_team_has_ball(&self) -> Team {
-        let on_sideline = self.is_on_sideline();
-        if self.ball.pos.x > self.field.mid_section {
+    pub fn which_team_has_ball(&self, ball_index: usize) -> Team {
+        let on_sideline = self.is_on_sideline(ball_index);
+        if self.balls[ball_index].pos.x > self.field.mid_section {
             if on_sideline != Sideline::Inside {
                 Team::One
             } else {
@@ -257,28 +668,204 @@ impl Game {
     }
 
     pub fn default() -> Self {
+        Self::new(Field::default(), Rect::new(0., 0., screen_width(), screen_height()), get_time())
+    }
+
+    /// Creates an empty game on the given field, framed by a camera with the given bounds, with
+    /// the clock at `time_passed`
+    pub(crate) fn new(field: Field, camera_bounds: Rect, time_passed: f64) -> Self {
         Game {
             players: vec![],
+            ball_animations: vec![],
             balls: vec![],
-            ball: Ball::default(),
             team_with_ball: Team::One,
-            field: Field::default(),
+            field,
             key_sets: HashMap::default(),
             gravity: Vec2::new(-2., -2.),
             keys_pressed: vec![],
             textures: vec![],
             zoom: Vec2::from(DEFAULT_ZOOM),
-            time_passed: get_time(),
-            gravity_line: screen_height() / 2.,
+            time_passed,
             world: World::new(),
+            camera: GameCamera::new(camera_bounds),
+            hit_stop: 0,
+            particles: Particles::new(&HashMap::new()),
+            winner: None,
+            ai: HashMap::new(),
+            selected_players: HashMap::new(),
+            teams: HashMap::new(),
+            court: CourtParams::default(),
+            ball_id: String::new(),
+            stats: vec![],
+            events: vec![],
+        }
+    }
+
+    /// Builds `world` for the round: the walls of the field and the obstacles of the court are
+    /// added as solids, and the collider of every player as an actor, so that moving players are
+    /// stopped by them
+    pub fn build_world(&mut self) {
+        self.world = World::new();
+        let obstacles = self.court.obstacles.iter().map(|obstacle| obstacle.rect(&self.field));
+        for rect in self.field.walls().into_iter().chain(obstacles) {
+            self.world.add_solid(vec2(rect.x, rect.y), rect.w as i32, rect.h as i32);
+        }
+        for player in &mut self.players {
+            let collider = player.collider();
+            player.actor = Some(self.world.add_actor(collider.point(), collider.w as i32, collider.h as i32));
+        }
+    }
+
+    /// Swaps what was reloaded into `Resources` into the round that is being played: the
+    /// characters of the players, the balls, the particle effects and the court. If a character or
+    /// the ball uses a texture that does not exist, nothing is changed and an error is returned
+    pub fn apply_resources(&mut self) -> Result<()> {
+        let resources = storage::get::<Resources>();
+        let characters: Vec<_> = self.players.iter()
+            .map(|player| resources.player_characters.iter().find(|character| character.id == player.character))
+            .collect();
+        let ball = resources.balls.iter().find(|ball| ball.id == self.ball_id);
+        let texture_ids = characters.iter().flatten()
+            .map(|character| &character.animation.texture_id)
+            .chain(ball.map(|ball| &ball.animation.texture_id));
+        for texture_id in texture_ids {
+            if !resources.textures.contains_key(texture_id) {
+                let msg = format!("There is no texture with the id '{}'", texture_id);
+                return Err(Error::new_message(ErrorKind::General, &msg));
+            }
+        }
+        for (player, character) in self.players.iter_mut().zip(characters) {
+            if let Some(character) = character {
+                player.reload_character(character);
+            }
+        }
+        if let Some(params) = ball {
+            for animation in &mut self.ball_animations {
+                *animation = params.animation_player();
+            }
+            for ball in &mut self.balls {
+                ball.physics = params.physics;
+                ball.r = params.physics.radius;
+            }
+        }
+        self.particles = Particles::new(&resources.particle_effects);
+        if let Some(court) = resources.courts.iter().find(|court| court.id == self.court.id) {
+            self.court = court.clone();
+        }
+        // the obstacles of the court may have moved
+        self.build_world();
+        Ok(())
+    }
+
+    /// The name of the team playing a side, falling back to "Team one" or "Team two"
+    pub fn team_name(&self, team: Team) -> String {
+        match (self.teams.get(&team), team) {
+            (Some(params), _) => params.name.clone(),
+            (None, Team::One) => "Team one".to_string(),
+            (None, Team::Two) => "Team two".to_string(),
+        }
+    }
+
+    /// Starts counting stats over, for every player in `players`
+    pub fn reset_stats(&mut self) {
+        self.stats = (0..self.players.len())
+            .map(|i| PlayerStats::new(&self.players[i].name, &self.team_name(self.team_of(i))))
+            .collect();
+    }
+
+    /// The kit color of the team playing a side
+    pub fn team_color(&self, team: Team) -> Color {
+        match (self.teams.get(&team), team) {
+            (Some(params), _) => params.primary_color,
+            (None, Team::One) => SKYBLUE,
+            (None, Team::Two) => ORANGE,
         }
     }
 
     pub fn set_zoom(&mut self, zoom: Option<[f32; 2]>) {
         self.zoom = Vec2::from(zoom.unwrap_or(DEFAULT_ZOOM));
     }
+
+    /// Declares the winner, once every player of the other team is out, and celebrates it
+    pub fn check_for_winner(&mut self) {
+        if self.winner.is_some() {
+            return;
+        }
+        for team in [Team::One, Team::Two] {
+            let is_other_team_out = self.team_range(other_team(team))
+                .filter(|&i| !self.players[i].is_outfielder)
+                .all(|i| self.players[i].life <= 0);
+            if is_other_team_out {
+                self.declare_winner(team);
+                return;
+            }
+        }
+    }
+
+    /// Ends the round in favour of `team`
+    pub fn declare_winner(&mut self, team: Team) {
+        self.winner = Some(team);
+        self.events.push(GameEvent::RoundWon { team });
+    }
+
+    /// The life that the players of a team have left, added up
+    pub fn team_life(&self, team: Team) -> i32 {
+        self.team_range(team).map(|i| self.players[i].life.max(0)).sum()
+    }
+
+    /// Takes a ball away from the team holding it, and puts it down on the other team's side
+    pub(crate) fn turn_over_ball(&mut self, ball_index: usize) {
+        let holder = match self.balls[ball_index].state {
+            BallState::OnPlayersHand(holder) => holder,
+            _ => return,
+        };
+        self.players[holder].has_ball = false;
+        let x = match self.team_of(holder) {
+            Team::One => self.field.mid_section + Self::TURNOVER_DISTANCE,
+            Team::Two => self.field.mid_section - Self::TURNOVER_DISTANCE,
+        };
+        let y = (self.field.top_edge + self.field.bottom_edge) / 2.;
+        let ball = &mut self.balls[ball_index];
+        ball.pos = Vec2::new(x, y);
+        ball.vel = Vec2::ZERO;
+        ball.grabbed_by = None;
+        ball.thrown_by = None;
+        ball.gravity_line = y + PLAYER_HEIGHT;
+        ball.state = BallState::OnGround;
+    }
+
+    /// Frames the balls and the active player of each team, applying the mouse wheel zoom on top
+    pub fn update_camera(&mut self) {
+        let mut targets: Vec<Rect> = self.balls.iter()
+            .map(|ball| Rect::new(ball.pos.x - ball.r, ball.pos.y - ball.r, ball.r * 2., ball.r * 2.))
+            .collect();
+        for team in [Team::One, Team::Two] {
+            if let Some(player_index) = self.get_active_player_for_team(team) {
+                let player = &mut self.players[player_index];
+                player.camera_box = Rect::new(player.pos.x, player.pos.y, PLAYER_WIDTH, PLAYER_HEIGHT);
+                targets.push(player.camera_box);
+            }
+        }
+        let zoom_scale = self.zoom.y / DEFAULT_ZOOM[1];
+        self.camera.update(&targets, zoom_scale);
+    }
 }
 
+/// The game itself reacts to hard hits with screen shake and hit stop, and celebrates a won round
+impl GameEventListener for Game {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::PlayerHit { velocity, max_speed, .. } => self.impact_feedback(velocity, max_speed),
+            GameEvent::RoundWon { team } => {
+                for i in self.team_range(team) {
+                    let position = self.players[i].pos + Vec2::new(PLAYER_WIDTH / 2., 0.);
+                    self.particles.spawn(Particles::VICTORY_EFFECT_ID, position);
+                }
+            }
+            _ => {}
+        }
+    }
+}
 
 pub(crate) fn calculate_movement(keys: [bool; 4]) -> (f32, FacingTo, Option<Vec2>) {
     let (key_up, key_right, key_down, key_left) = (keys[0], keys[1], keys[2], keys[3]);
@@ -303,6 +890,15 @@ pub(crate) fn calculate_movement(keys: [bool; 4]) -> (f32, FacingTo, Option<Vec2
     }
 }
 
+/// The fraction of a move, from `start` to `end`, at which `limit` is crossed. A move that starts
+/// past the limit already crosses it at 0
+fn crossing_time(start: f32, end: f32, limit: f32) -> f32 {
+    if start == end {
+        return 0.;
+    }
+    ((limit - start) / (end - start)).clamp(0., 1.)
+}
+
 pub(crate) fn other_team(team: Team) -> Team {
     match team {
         Team::One => { Team::Two }
@@ -310,3 +906,135 @@ pub(crate) fn other_team(team: Team) -> Team {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::animations::{AnimationMetadata, AnimationParams};
+
+    /// A player with an empty texture, so that no window is needed
+    fn test_player(id: u8, x: f32, facing_to: FacingTo) -> Player {
+        let params = AnimationParams {
+            frame_size: Some(uvec2(PLAYER_WIDTH as u32, PLAYER_HEIGHT as u32)),
+            animations: vec![AnimationMetadata {
+                id: Player::IDLE_ANIMATION_ID.to_string(),
+                row: 0,
+                frames: 1,
+                fps: 1,
+                is_looping: true,
+            }],
+            ..Default::default()
+        };
+        let animation_player = AnimationPlayer::with_texture(params, Texture2D::empty(), None);
+        let pos = vec2(x, 300.);
+        Player::new(id, pos, 0., Vec2::ZERO, 100, false, WHITE, facing_to, animation_player)
+    }
+
+    /// A game with one player on each team, facing each other across the mid line
+    fn two_player_game() -> Game {
+        let screen_size = vec2(800., 600.);
+        let mut game = Game::new(Field::new(screen_size), Rect::new(0., 0., screen_size.x, screen_size.y), 0.);
+        game.players.push(test_player(0, 200., FacingTo::FacingRight));
+        game.players.push(test_player(1, 500., FacingTo::FacingLeft));
+        game.reset_stats();
+        let mut ball = Ball::default();
+        ball.r = ball.physics.radius;
+        game.balls.push(ball);
+        game
+    }
+
+    /// Throws the ball from the first player, so that it ends up inside the second player this frame
+    fn throw_at_second_player(game: &mut Game) {
+        let target = game.players[1].collider();
+        let center = vec2(target.x + target.w / 2., target.y + target.h / 2.);
+        let ball = &mut game.balls[0];
+        ball.grabbed_by = Some(0);
+        ball.throwing(vec2(1., 0.), vec2(target.x - 30., center.y), FacingTo::FacingRight);
+        ball.prev_pos = ball.pos;
+        ball.pos = center;
+    }
+
+    #[test]
+    fn test_build_world_actors() {
+        let mut game = two_player_game();
+        game.players[1].collider_offset = vec2(6., -4.);
+        game.build_world();
+        for player in &game.players {
+            let collider = player.collider();
+            let actor = player.actor.expect("every player has an actor");
+            assert_eq!(game.world.actor_pos(actor), collider.point());
+            assert_eq!(player.pos + player.collider_origin(), collider.point());
+        }
+        // the actor is as big as the collider, so a solid just below the collider blocks it
+        let collider = game.players[0].collider();
+        let actor = game.players[0].actor.unwrap();
+        game.world.add_solid(vec2(collider.x, collider.y + collider.h + 1.), 1, 1);
+        assert!(!game.world.move_v(actor, 2.));
+    }
+
+    #[test]
+    fn test_hit_events() {
+        let mut game = two_player_game();
+        throw_at_second_player(&mut game);
+        game.is_the_ball_hitting_any_player(0);
+        let events = std::mem::take(&mut game.events);
+        assert!(matches!(
+            events.as_slice(),
+            [GameEvent::PlayerHit { ball: 0, player: 1, thrower: Some(0), damage, .. }] if *damage > 0
+        ), "{:?}", events);
+        assert_eq!(game.players[1].life, 100 - game.balls[0].physics.base_damage);
+        assert!(!game.balls[0].is_held());
+    }
+
+    #[test]
+    fn test_catch_events() {
+        let mut game = two_player_game();
+        game.players[1].state = PlayerState::Catching;
+        throw_at_second_player(&mut game);
+        game.is_the_ball_hitting_any_player(0);
+        let events = std::mem::take(&mut game.events);
+        assert!(matches!(
+            events.as_slice(),
+            [GameEvent::BallCaught { ball: 0, player: 1, from_opponent: true, .. }]
+        ), "{:?}", events);
+        assert_eq!(game.balls[0].state, BallState::OnPlayersHand(1));
+        assert_eq!(game.players[1].life, 100);
+    }
+
+    #[test]
+    fn test_out_of_bounds_events() {
+        let mut game = two_player_game();
+        let top = game.field.top_edge;
+        let ball = &mut game.balls[0];
+        ball.grabbed_by = Some(0);
+        ball.throwing(vec2(0., -1.), vec2(400., top + 20.), FacingTo::FacingRight);
+        ball.prev_pos = ball.pos;
+        ball.pos = vec2(400., top - 40.);
+        game.is_ball_hitting_boundary(0);
+        let events = std::mem::take(&mut game.events);
+        assert!(matches!(events.as_slice(), [GameEvent::BallOutOfBounds { ball: 0, .. }]), "{:?}", events);
+        assert!(matches!(game.balls[0].state, BallState::AfterHittingBoundary { .. }));
+        // the ball is moved back to the edge it crossed
+        assert!(game.balls[0].pos.y > top - 40.);
+    }
+
+    #[test]
+    fn test_switch_player() {
+        let mut game = two_player_game();
+        // team one is the first half of `players`, so its second player goes in the middle
+        game.players.insert(1, test_player(2, 100., FacingTo::FacingRight));
+        game.players.push(test_player(3, 600., FacingTo::FacingLeft));
+        game.balls[0].state = BallState::OnGround;
+        game.balls[0].pos = vec2(0., 300.);
+        assert_eq!(game.get_active_player_for_team(Team::One), Some(1));
+        game.switch_player(Team::One);
+        assert_eq!(game.get_active_player_for_team(Team::One), Some(0));
+        game.switch_player(Team::One);
+        assert_eq!(game.get_active_player_for_team(Team::One), Some(1));
+        // a selected player that is out hands control back to the nearest one
+        game.players[1].life = 0;
+        assert_eq!(game.get_active_player_for_team(Team::One), Some(0));
+        game.switch_player(Team::One);
+        assert_eq!(game.get_active_player_for_team(Team::One), Some(0));
+    }
+}
@@ -0,0 +1,205 @@
+//! This implements particle effects on top of `macroquad_particles`. Emitter presets are declared in
+//! the `particle_effects.json` file, as `ParticleEmitterMetadata`, and are spawned by their id, from
+//! gameplay code, through `Particles::spawn`.
+
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use macroquad_particles::{BlendMode, ColorCurve, EmissionShape, EmitterConfig, EmittersCache};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::events::{GameEvent, GameEventListener};
+use crate::json;
+
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EmissionShapeMetadata {
+    #[default]
+    Point,
+    Rect { width: f32, height: f32 },
+    Sphere { radius: f32 },
+}
+
+impl From<EmissionShapeMetadata> for EmissionShape {
+    fn from(other: EmissionShapeMetadata) -> Self {
+        match other {
+            EmissionShapeMetadata::Point => EmissionShape::Point,
+            EmissionShapeMetadata::Rect { width, height } => EmissionShape::Rect { width, height },
+            EmissionShapeMetadata::Sphere { radius } => EmissionShape::Sphere { radius },
+        }
+    }
+}
+
+/// This is a declaration of a particle emitter preset. It mirrors the relevant parts of
+/// `macroquad_particles::EmitterConfig`, which is what it is converted into when an emitter is built.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParticleEmitterMetadata {
+    /// The id of the preset. This is what gameplay code uses to spawn it.
+    pub id: String,
+    /// The offset from the position the effect is spawned at
+    #[serde(default, with = "json::vec2_def")]
+    pub offset: Vec2,
+    #[serde(default)]
+    pub emission_shape: EmissionShapeMetadata,
+    /// If this is true, the emitter will emit `amount` particles once and then stop
+    #[serde(default = "ParticleEmitterMetadata::default_one_shot")]
+    pub one_shot: bool,
+    /// The lifetime of a particle, in seconds
+    #[serde(default = "ParticleEmitterMetadata::default_lifetime")]
+    pub lifetime: f32,
+    #[serde(default)]
+    pub lifetime_randomness: f32,
+    /// 0 will spread particle emission over the lifetime, while 1 will emit them all at once
+    #[serde(default)]
+    pub explosiveness: f32,
+    #[serde(default = "ParticleEmitterMetadata::default_amount")]
+    pub amount: u32,
+    #[serde(
+        default = "ParticleEmitterMetadata::default_initial_direction",
+        with = "json::vec2_def"
+    )]
+    pub initial_direction: Vec2,
+    /// The spread of the initial direction, in radians
+    #[serde(default)]
+    pub initial_direction_spread: f32,
+    #[serde(default = "ParticleEmitterMetadata::default_initial_velocity")]
+    pub initial_velocity: f32,
+    #[serde(default)]
+    pub initial_velocity_randomness: f32,
+    #[serde(default)]
+    pub linear_accel: f32,
+    #[serde(default = "ParticleEmitterMetadata::default_size")]
+    pub size: f32,
+    #[serde(default)]
+    pub size_randomness: f32,
+    /// If this is true, overlapping particles will have their colors added, instead of blended
+    #[serde(default, skip_serializing_if = "json::is_false")]
+    pub is_additive: bool,
+    #[serde(default = "ParticleEmitterMetadata::default_color", with = "json::ColorDef")]
+    pub start_color: Color,
+    #[serde(default = "ParticleEmitterMetadata::default_color", with = "json::ColorDef")]
+    pub mid_color: Color,
+    #[serde(default = "ParticleEmitterMetadata::default_color", with = "json::ColorDef")]
+    pub end_color: Color,
+    #[serde(default, with = "json::vec2_def")]
+    pub gravity: Vec2,
+}
+
+impl ParticleEmitterMetadata {
+    const DEFAULT_LIFETIME: f32 = 1.0;
+    const DEFAULT_AMOUNT: u32 = 8;
+    const DEFAULT_INITIAL_VELOCITY: f32 = 50.0;
+    const DEFAULT_SIZE: f32 = 4.0;
+
+    pub fn default_one_shot() -> bool {
+        true
+    }
+
+    pub fn default_lifetime() -> f32 {
+        Self::DEFAULT_LIFETIME
+    }
+
+    pub fn default_amount() -> u32 {
+        Self::DEFAULT_AMOUNT
+    }
+
+    pub fn default_initial_direction() -> Vec2 {
+        vec2(0., -1.)
+    }
+
+    pub fn default_initial_velocity() -> f32 {
+        Self::DEFAULT_INITIAL_VELOCITY
+    }
+
+    pub fn default_size() -> f32 {
+        Self::DEFAULT_SIZE
+    }
+
+    pub fn default_color() -> Color {
+        WHITE
+    }
+}
+
+impl From<ParticleEmitterMetadata> for EmitterConfig {
+    fn from(other: ParticleEmitterMetadata) -> Self {
+        EmitterConfig {
+            emission_shape: other.emission_shape.into(),
+            one_shot: other.one_shot,
+            lifetime: other.lifetime,
+            lifetime_randomness: other.lifetime_randomness,
+            explosiveness: other.explosiveness,
+            amount: other.amount,
+            initial_direction: other.initial_direction,
+            initial_direction_spread: other.initial_direction_spread,
+            initial_velocity: other.initial_velocity,
+            initial_velocity_randomness: other.initial_velocity_randomness,
+            linear_accel: other.linear_accel,
+            size: other.size,
+            size_randomness: other.size_randomness,
+            blend_mode: if other.is_additive {
+                BlendMode::Additive
+            } else {
+                BlendMode::Alpha
+            },
+            colors_curve: ColorCurve {
+                start: other.start_color,
+                mid: other.mid_color,
+                end: other.end_color,
+            },
+            gravity: other.gravity,
+            ..Default::default()
+        }
+    }
+}
+
+/// Holds one `EmittersCache` per preset, so that spawning the same effect many times will reuse
+/// emitters, once their particles have died.
+pub struct Particles {
+    emitters: HashMap<String, (EmittersCache, Vec2)>,
+}
+
+impl Particles {
+    pub const DUST_EFFECT_ID: &'static str = "dust";
+    pub const HIT_EFFECT_ID: &'static str = "hit_sparks";
+    pub const CATCH_EFFECT_ID: &'static str = "catch_burst";
+    pub const SUPER_SHOT_TRAIL_EFFECT_ID: &'static str = "super_shot_trail";
+    pub const VICTORY_EFFECT_ID: &'static str = "victory_confetti";
+
+    pub fn new(presets: &HashMap<String, ParticleEmitterMetadata>) -> Self {
+        let emitters = presets
+            .iter()
+            .map(|(id, meta)| {
+                let cache = EmittersCache::new(meta.clone().into());
+                (id.clone(), (cache, meta.offset))
+            })
+            .collect();
+
+        Particles { emitters }
+    }
+
+    /// Spawns the effect with the given id at `position`. Unknown ids are ignored, so that a
+    /// missing preset will not crash the game.
+    pub fn spawn(&mut self, id: &str, position: Vec2) {
+        if let Some((cache, offset)) = self.emitters.get_mut(id) {
+            cache.spawn(position + *offset);
+        }
+    }
+
+    pub fn draw(&mut self) {
+        for (cache, _) in self.emitters.values_mut() {
+            cache.draw();
+        }
+    }
+}
+
+impl GameEventListener for Particles {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::PlayerHit { pos, .. } => self.spawn(Self::HIT_EFFECT_ID, pos),
+            GameEvent::BallCaught { pos, .. } => self.spawn(Self::CATCH_EFFECT_ID, pos),
+            GameEvent::Footstep { pos, .. } => self.spawn(Self::DUST_EFFECT_ID, pos),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! This implements the pause menu, which is drawn on top of a match while the simulation is frozen.
+//! Choices that throw away progress have to be confirmed.
+//!
+//! There is no network play yet. Once there is, opening and closing this menu should be sent to
+//! the other peers, so that the simulation is paused on every machine at the same frame.
+
+use macroquad::prelude::*;
+
+use crate::game::scene::draw_menu;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PauseChoice {
+    Resume,
+    RestartRound,
+    RestartMatch,
+    Settings,
+    QuitToMenu,
+}
+
+impl PauseChoice {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PauseChoice::Resume => "Resume",
+            PauseChoice::RestartRound => "Restart round",
+            PauseChoice::RestartMatch => "Restart match",
+            PauseChoice::Settings => "Settings",
+            PauseChoice::QuitToMenu => "Quit to menu",
+        }
+    }
+
+    /// Choices that throw away progress, and have to be confirmed
+    pub fn is_destructive(&self) -> bool {
+        matches!(
+            self,
+            PauseChoice::RestartRound | PauseChoice::RestartMatch | PauseChoice::QuitToMenu
+        )
+    }
+}
+
+pub struct PauseMenu {
+    selected: usize,
+    /// The destructive choice that is waiting for a confirmation, if any
+    confirming: Option<PauseChoice>,
+    confirm_selected: usize,
+}
+
+impl PauseMenu {
+    const CHOICES: [PauseChoice; 5] = [
+        PauseChoice::Resume,
+        PauseChoice::RestartRound,
+        PauseChoice::RestartMatch,
+        PauseChoice::Settings,
+        PauseChoice::QuitToMenu,
+    ];
+    const CONFIRM_ITEMS: [&'static str; 2] = ["No", "Yes"];
+    const OVERLAY_COLOR: Color = Color::new(0., 0., 0., 0.5);
+
+    pub fn new() -> Self {
+        PauseMenu {
+            selected: 0,
+            confirming: None,
+            confirm_selected: 0,
+        }
+    }
+
+    /// Reads the menu keys, and returns the choice that was made this frame, if any. Escape
+    /// resumes the match, or backs out of a confirmation.
+    pub fn update(&mut self) -> Option<PauseChoice> {
+        if let Some(choice) = self.confirming {
+            if is_key_pressed(KeyCode::Escape) {
+                self.confirming = None;
+            } else if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::Down) {
+                self.confirm_selected = 1 - self.confirm_selected;
+            } else if is_key_pressed(KeyCode::Enter) {
+                self.confirming = None;
+                if self.confirm_selected == 1 {
+                    return Some(choice);
+                }
+            }
+            return None;
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            return Some(PauseChoice::Resume);
+        }
+        let len = Self::CHOICES.len();
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + len - 1) % len;
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % len;
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            let choice = Self::CHOICES[self.selected];
+            if choice.is_destructive() {
+                // default to "No", so that a double tap of Enter does not throw anything away
+                self.confirming = Some(choice);
+                self.confirm_selected = 0;
+            } else {
+                return Some(choice);
+            }
+        }
+        None
+    }
+
+    /// Draws the menu on top of whatever is on screen
+    pub fn draw(&self) {
+        draw_rectangle(0., 0., screen_width(), screen_height(), Self::OVERLAY_COLOR);
+        match self.confirming {
+            Some(choice) => {
+                let title = format!("{}?", choice.label());
+                draw_menu(&title, &Self::CONFIRM_ITEMS, self.confirm_selected);
+            }
+            None => {
+                let items: Vec<&str> = Self::CHOICES.iter().map(PauseChoice::label).collect();
+                draw_menu("Paused", &items, self.selected);
+            }
+        }
+    }
+}
+
+impl Default for PauseMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,10 +2,11 @@ use std::collections::HashMap;
 use macroquad::color::Color;
 use macroquad::math::Rect;
 use macroquad_platformer::Actor;
-use crate::{AnimationPlayer, calculate_movement, FacingTo, HasDirection, KeyCode, PlayerAction, valid_position, Vec2};
+use crate::{AnimationPlayer, calculate_movement, FacingTo, HasDirection, KeyCode, PlayerAction, Vec2};
 use crate::game::ball::Ball;
 use crate::game::character::PlayerCharacterParams;
 use crate::game::player::PlayerState::Walking;
+use crate::game::team::kit_tint;
 
 #[derive(PartialEq)]
 pub enum PlayerState {
@@ -33,6 +34,12 @@ pub struct Player {
     pub(crate) jumping: bool,
     pub(crate) has_ball: bool,
     pub(crate) life: i32,
+    /// The life the player started the round with
+    pub(crate) max_life: i32,
+    /// The id of the character, from `player_characters.json`
+    pub(crate) character: String,
+    /// The name of the character, as shown in the HUD
+    pub(crate) name: String,
     pub(crate) running: bool,
     pub(crate) ready_to_catch: bool,
     pub(crate) camera_box: Rect,
@@ -40,10 +47,83 @@ pub struct Player {
     pub(crate) is_hit: bool,
     pub(crate) catch_grace_time: f64,
     pub(crate) state: PlayerState,
-
+    pub(crate) dust_time: f64,
+    /// The time, in seconds, that the player has been moving without stopping
+    pub(crate) run_time: f32,
+    /// Outfielders stand behind the other team's back line, and can not be knocked out
+    pub(crate) is_outfielder: bool,
+    /// The walking speed, in pixels per second, from the character's `move_speed`
+    pub(crate) move_speed: f32,
+    /// The size of the hitbox, from the character's `collider_size`
+    pub(crate) collider_size: Vec2,
+    /// Where the hitbox is, relative to the bottom center of the sprite, from the character's
+    /// `collider_offset`
+    pub(crate) collider_offset: Vec2,
+    /// Where the ball is held and released, from the character's `weapon_mount`
+    pub(crate) weapon_mount: Vec2,
+    /// This is multiplied with the damage of the balls the player throws
+    pub(crate) power: f32,
+    /// The player's body in `Game::world`, which moves them, once the world is built
+    pub(crate) actor: Option<Actor>,
 }
 
 impl Player {
+    /// Takes on the stats of the given character
+    pub(crate) fn set_character(&mut self, params: &PlayerCharacterParams) {
+        self.character = params.id.clone();
+        self.name = params.name.clone();
+        self.move_speed = params.move_speed;
+        self.collider_size = params.collider_size;
+        self.collider_offset = params.collider_offset;
+        self.weapon_mount = params.weapon_mount;
+    }
+
+    /// Takes on the changes to a character that was reloaded from `player_characters.json`. The
+    /// speed is kept, as it may have been changed by the team's roster
+    pub(crate) fn reload_character(&mut self, params: &PlayerCharacterParams) {
+        let move_speed = self.move_speed;
+        self.set_character(params);
+        self.move_speed = move_speed;
+        self.animation_player = params.animation_player();
+        self.animation_player.set_tint(kit_tint(self.color));
+        self.set_animation();
+    }
+
+    /// The hitbox of the player. Its bottom center is put at the bottom center of the sprite, as
+    /// it is drawn, moved by the collider offset, so that it follows the sprite of the character
+    pub(crate) fn collider(&self) -> Rect {
+        let sprite_size = self.animation_player.get_size();
+        let offset = if self.facing_to_before == FacingTo::FacingLeft {
+            Vec2::new(-self.collider_offset.x, self.collider_offset.y)
+        } else {
+            self.collider_offset
+        };
+        let bottom_center = self.pos + self.animation_player.get_offset()
+            + Vec2::new(sprite_size.x / 2., sprite_size.y)
+            + offset;
+        Rect::new(
+            bottom_center.x - self.collider_size.x / 2.,
+            bottom_center.y - self.collider_size.y,
+            self.collider_size.x,
+            self.collider_size.y,
+        )
+    }
+
+    /// Where the top left corner of the hitbox is, relative to `pos`
+    pub(crate) fn collider_origin(&self) -> Vec2 {
+        self.collider().point() - self.pos
+    }
+
+    /// The point where the ball is held, which is mirrored when the player is facing left
+    pub(crate) fn weapon_mount_position(&self) -> Vec2 {
+        let center = self.pos + self.animation_player.get_offset() + self.animation_player.get_size() / 2.;
+        if self.facing_to_before == FacingTo::FacingLeft {
+            center + Vec2::new(-self.weapon_mount.x, self.weapon_mount.y)
+        } else {
+            center + self.weapon_mount
+        }
+    }
+
     pub(crate) fn set_animation(&mut self) {
         let state = match self.state {
             PlayerState::Idle => Player::IDLE_ANIMATION_ID,
@@ -77,6 +157,12 @@ impl Player {
     pub const CATCH_ANIMATION_ID: &'static str = "catching";
 
     pub const CATCH_GRACE_TIME: f64 = 5.;
+    /// The time between dust clouds, while the player is moving
+    pub const DUST_INTERVAL: f64 = 0.2;
+    /// After moving for this long, the player starts running, and a throw becomes a super shot
+    pub const RUN_UP_TIME: f32 = 0.6;
+    /// Running is this much faster than walking
+    pub const RUN_SPEED_FACTOR: f32 = 1.4;
 
     pub fn new(id:u8, pos: Vec2, rotation: f32,
                vel: Vec2, life: i32, has_ball: bool, color: Color,
@@ -88,12 +174,15 @@ impl Player {
             rotation,
             vel,
             life,
+            max_life: life,
+            character: String::new(),
+            name: String::new(),
             running: false,
             is_hit: false,
             has_ball,
             color,
             facing_to,
-            facing_to_before: FacingTo::FacingLeft,
+            facing_to_before: facing_to,
             ducking: false,
             jumping: false,
             ready_to_catch: false,
@@ -101,6 +190,15 @@ impl Player {
             animation_player,
             catch_grace_time: 0.,
             state: PlayerState::Idle,
+            dust_time: 0.,
+            run_time: 0.,
+            is_outfielder: false,
+            move_speed: PlayerCharacterParams::default_move_speed(),
+            collider_size: PlayerCharacterParams::default_collider_size(),
+            collider_offset: PlayerCharacterParams::default_collider_offset(),
+            weapon_mount: PlayerCharacterParams::default_weapon_mount(),
+            power: 1.,
+            actor: None,
         }
     }
 
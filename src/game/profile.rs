@@ -0,0 +1,508 @@
+//! This implements `Profile`, which holds everything that is saved for a player: their name, their
+//! key bindings, their campaign progress, with the characters it unlocked, and their lifetime
+//! stats. Profiles are saved as JSON files in the user's data directory, one file per profile, and
+//! the active profile is kept in storage.
+//!
+//! Every profile file has a version. When the format changes, `Profile::VERSION` goes up, and a
+//! migration is added to `MIGRATIONS`, which upgrades a file from the version before, so that
+//! older saves keep working. Files are migrated as raw JSON, before they are deserialized.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use macroquad::prelude::collections::storage;
+use macroquad::prelude::*;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::game::campaign::CampaignProgress;
+use crate::game::input::{key_code, key_name};
+use crate::game::match_state::MatchResult;
+use crate::game::resources::Resources;
+use crate::game::settings::{save_settings, Settings};
+use crate::json::{deserialize_json_bytes, serialize_json_bytes};
+use crate::{PlayerAction, Team};
+
+/// The name of the directory, in the user's data directory, that the game saves in
+const APP_DIR: &str = "super-dodge-ball";
+const PROFILES_DIR: &str = "profiles";
+
+/// Upgrades a profile from the version at its index, to the version after it
+const MIGRATIONS: [fn(Value) -> Value; 1] = [migrate_campaign_progress];
+
+/// Version 0 is the `campaign_progress.json` file, that was saved before there were profiles. It
+/// becomes the campaign progress of a profile
+fn migrate_campaign_progress(value: Value) -> Value {
+    json!({
+        "version": 1,
+        "name": Profile::DEFAULT_NAME,
+        "campaign": value,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    /// The version of the format the profile was saved in
+    pub version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub key_bindings: KeyBindings,
+    #[serde(default)]
+    pub campaign: CampaignProgress,
+    #[serde(default)]
+    pub stats: LifetimeStats,
+}
+
+/// The keys a player controls their team with, by the names in `input::KEY_NAMES`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyBindings {
+    pub up: String,
+    pub down: String,
+    pub left: String,
+    pub right: String,
+    pub a: String,
+    pub b: String,
+    #[serde(default = "KeyBindings::default_switch")]
+    pub switch: String,
+}
+
+impl KeyBindings {
+    /// The actions, in the order they are bound in
+    pub const ACTIONS: [PlayerAction; 7] = [
+        PlayerAction::MoveUp,
+        PlayerAction::MoveDown,
+        PlayerAction::MoveLeft,
+        PlayerAction::MoveRight,
+        PlayerAction::A,
+        PlayerAction::B,
+        PlayerAction::Switch,
+    ];
+
+    const DEFAULT_SWITCH: &'static str = "B";
+
+    pub fn default_switch() -> String {
+        Self::DEFAULT_SWITCH.to_string()
+    }
+
+    fn binding_mut(&mut self, action: &PlayerAction) -> &mut String {
+        match action {
+            PlayerAction::MoveUp => &mut self.up,
+            PlayerAction::MoveDown => &mut self.down,
+            PlayerAction::MoveLeft => &mut self.left,
+            PlayerAction::MoveRight => &mut self.right,
+            PlayerAction::A => &mut self.a,
+            PlayerAction::B => &mut self.b,
+            PlayerAction::Switch => &mut self.switch,
+        }
+    }
+
+    pub fn binding(&self, action: &PlayerAction) -> &str {
+        match action {
+            PlayerAction::MoveUp => &self.up,
+            PlayerAction::MoveDown => &self.down,
+            PlayerAction::MoveLeft => &self.left,
+            PlayerAction::MoveRight => &self.right,
+            PlayerAction::A => &self.a,
+            PlayerAction::B => &self.b,
+            PlayerAction::Switch => &self.switch,
+        }
+    }
+
+    /// The key bound to an action. Unknown key names fall back to the default binding
+    pub fn key(&self, action: &PlayerAction) -> KeyCode {
+        key_code(self.binding(action))
+            .or_else(|| key_code(Self::default().binding(action)))
+            .unwrap_or(KeyCode::Unknown)
+    }
+
+    pub fn bind(&mut self, action: &PlayerAction, key: KeyCode) {
+        if let Some(name) = key_name(key) {
+            *self.binding_mut(action) = name.to_string();
+        }
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            up: "W".to_string(),
+            down: "S".to_string(),
+            left: "A".to_string(),
+            right: "D".to_string(),
+            a: "N".to_string(),
+            b: "M".to_string(),
+            switch: Self::default_switch(),
+        }
+    }
+}
+
+/// Totals over every match played with a profile
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LifetimeStats {
+    #[serde(default)]
+    pub matches_played: u32,
+    #[serde(default)]
+    pub matches_won: u32,
+    #[serde(default)]
+    pub rounds_won: u32,
+    #[serde(default)]
+    pub rounds_lost: u32,
+    /// The time, in seconds, spent playing matches
+    #[serde(default)]
+    pub time_played: f32,
+}
+
+impl Profile {
+    pub const VERSION: u32 = MIGRATIONS.len() as u32;
+    pub const DEFAULT_NAME: &'static str = "Player";
+
+    pub fn new(name: &str) -> Self {
+        Profile {
+            version: Self::VERSION,
+            name: name.to_string(),
+            key_bindings: KeyBindings::default(),
+            campaign: CampaignProgress::default(),
+            stats: LifetimeStats::default(),
+        }
+    }
+
+    /// The id of the profile, which is its file name. This is the name, with everything but
+    /// letters and digits replaced
+    pub fn id(&self) -> String {
+        self.name
+            .trim()
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect()
+    }
+
+    /// Adds a finished match, played as `team`, to the lifetime stats
+    pub fn record_match(&mut self, result: &MatchResult, team: Team) {
+        let stats = &mut self.stats;
+        stats.matches_played += 1;
+        if result.winner == team {
+            stats.matches_won += 1;
+        }
+        for (round_team, wins) in &result.round_wins {
+            if *round_team == team {
+                stats.rounds_won += wins;
+            } else {
+                stats.rounds_lost += wins;
+            }
+        }
+        stats.time_played += result.time_played;
+    }
+
+    /// Parses a profile, migrating it from older versions first. Files without a version are
+    /// treated as version 0
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut value: Value = deserialize_json_bytes(bytes)?;
+        let version = value.get("version").and_then(Value::as_u64).unwrap_or(0) as usize;
+        if version > MIGRATIONS.len() {
+            let msg = format!("The profile was saved by a newer version of the game (version {})", version);
+            return Err(Error::new_message(ErrorKind::Parsing, &msg));
+        }
+        for migration in &MIGRATIONS[version..] {
+            value = migration(value);
+        }
+        let profile = serde_json::from_value(value)?;
+        Ok(profile)
+    }
+}
+
+/// The directory the game saves in, inside the user's data directory. This follows the convention
+/// of each platform, going by the environment, and falls back to the working directory
+pub fn data_dir() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| Path::new(&home).join("Library").join("Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share")))
+    };
+    base.unwrap_or_else(|| PathBuf::from(".")).join(APP_DIR)
+}
+
+fn profiles_dir() -> PathBuf {
+    data_dir().join(PROFILES_DIR)
+}
+
+fn profile_path(id: &str) -> PathBuf {
+    profiles_dir()
+        .join(id)
+        .with_extension(Resources::RESOURCE_FILES_EXTENSION)
+}
+
+/// Writes a profile to its file, in the profiles directory
+pub fn save_profile(profile: &Profile) -> Result<()> {
+    fs::create_dir_all(profiles_dir())?;
+    let bytes = serialize_json_bytes(profile)?;
+    fs::write(profile_path(&profile.id()), bytes)?;
+    Ok(())
+}
+
+/// Every profile that can be read, sorted by name, and the errors of the files that can not be.
+/// Those are skipped, so that one broken file does not keep the others from loading
+pub fn load_profiles() -> (Vec<Profile>, Vec<String>) {
+    let entries = match fs::read_dir(profiles_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return (Vec::new(), Vec::new()),
+    };
+    let mut errors = Vec::new();
+    let mut profiles: Vec<Profile> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(Resources::RESOURCE_FILES_EXTENSION))
+        .filter_map(|path| {
+            let profile = fs::read(&path).map_err(Error::from).and_then(|bytes| Profile::from_bytes(&bytes));
+            match profile {
+                Ok(profile) => Some(profile),
+                Err(err) => {
+                    errors.push(format!("Could not load the profile {}: {}", path.display(), err));
+                    None
+                }
+            }
+        })
+        .collect();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    (profiles, errors)
+}
+
+/// Loads the profile picked in the settings, and stores it. If there are no profiles yet, a new
+/// one is made, which takes over the campaign progress saved in `assets_dir` before there were
+/// profiles
+pub fn load_profile(assets_dir: &str) {
+    let (profiles, errors) = load_profiles();
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+    let picked = storage::get::<Settings>().profile.clone();
+    let profile = picked
+        .and_then(|id| profiles.iter().find(|profile| profile.id() == id).cloned())
+        .or_else(|| profiles.first().cloned())
+        .unwrap_or_else(|| {
+            let legacy_path = Path::new(assets_dir)
+                .join(CampaignProgress::CAMPAIGN_PROGRESS_FILE)
+                .with_extension(Resources::RESOURCE_FILES_EXTENSION);
+            let profile = fs::read(legacy_path)
+                .ok()
+                .and_then(|bytes| Profile::from_bytes(&bytes).ok())
+                .unwrap_or_else(|| Profile::new(Profile::DEFAULT_NAME));
+            if let Err(err) = save_profile(&profile) {
+                eprintln!("Could not save the profile: {}", err);
+            }
+            profile
+        });
+    storage::store(profile);
+}
+
+/// Makes a profile the active one, and remembers it in the settings. The profile is active even
+/// if the settings could not be saved
+pub fn set_active_profile(profile: Profile) -> Result<()> {
+    let saved = {
+        let mut settings = storage::get_mut::<Settings>();
+        settings.profile = Some(profile.id());
+        save_settings(&settings)
+    };
+    storage::store(profile);
+    saved
+}
+
+/// Applies a change to the active profile, and saves it. The change is kept even if it could not
+/// be saved
+pub fn update_profile<F: FnOnce(&mut Profile)>(f: F) -> Result<()> {
+    let mut profile = storage::get_mut::<Profile>();
+    f(&mut profile);
+    save_profile(&profile)
+}
+
+const TEXT_SIZE: f32 = 24.;
+const LINE_HEIGHT: f32 = 30.;
+const MAX_NAME_LENGTH: usize = 20;
+
+/// Shows the profiles screen, where the active profile is picked, new profiles are made, and key
+/// bindings are changed, until it is left with Escape
+pub async fn profiles_menu() {
+    let (mut profiles, errors) = load_profiles();
+    let mut selected = {
+        let active = storage::get::<Profile>().id();
+        profiles.iter().position(|profile| profile.id() == active).unwrap_or(0)
+    };
+    // the name of the profile being made, if one is
+    let mut new_name: Option<String> = None;
+    // the action whose key is being bound, if one is
+    let mut binding: Option<usize> = None;
+    let mut status = errors.join(" / ");
+    loop {
+        // `profiles.len()` stands for a new profile
+        let rows = profiles.len() + 1;
+        if let Some(name) = &mut new_name {
+            while let Some(character) = get_char_pressed() {
+                if !character.is_control() && name.chars().count() < MAX_NAME_LENGTH {
+                    name.push(character);
+                }
+            }
+            if is_key_pressed(KeyCode::Backspace) {
+                name.pop();
+            }
+            if is_key_pressed(KeyCode::Escape) {
+                new_name = None;
+            } else if is_key_pressed(KeyCode::Enter) {
+                let profile = Profile::new(name.trim());
+                let id = profile.id();
+                if id.is_empty() {
+                    status = "The profile needs a name".to_string();
+                } else if profiles.iter().any(|other| other.id() == id) {
+                    status = "There already is a profile with that name".to_string();
+                } else {
+                    status = match save_profile(&profile) {
+                        Ok(()) => format!("Made {}", profile.name),
+                        Err(err) => format!("Could not save the profile: {}", err),
+                    };
+                    profiles = load_profiles().0;
+                    selected = profiles.iter().position(|other| other.id() == id).unwrap_or(0);
+                    new_name = None;
+                }
+            }
+        } else if let Some(action) = binding {
+            if is_key_pressed(KeyCode::Escape) {
+                binding = None;
+            } else if let Some(key) = get_last_key_pressed() {
+                if key_name(key).is_some() {
+                    if let Err(err) = update_profile(|profile| profile.key_bindings.bind(&KeyBindings::ACTIONS[action], key)) {
+                        status = format!("Could not save the profile: {}", err);
+                    }
+                    binding = Some(action + 1).filter(|&next| next < KeyBindings::ACTIONS.len());
+                }
+            }
+        } else {
+            // typed characters are queued up, so they are thrown away when not typing a name
+            while get_char_pressed().is_some() {}
+            if is_key_pressed(KeyCode::Escape) {
+                return;
+            }
+            if is_key_pressed(KeyCode::Up) {
+                selected = (selected + rows - 1) % rows;
+            }
+            if is_key_pressed(KeyCode::Down) {
+                selected = (selected + 1) % rows;
+            }
+            if is_key_pressed(KeyCode::Enter) {
+                if selected == profiles.len() {
+                    new_name = Some(String::new());
+                } else {
+                    status = match set_active_profile(profiles[selected].clone()) {
+                        Ok(()) => format!("Playing as {}", profiles[selected].name),
+                        Err(err) => format!("Playing as {}, but could not save the settings: {}", profiles[selected].name, err),
+                    };
+                }
+            }
+            if is_key_pressed(KeyCode::K) {
+                binding = Some(0);
+            }
+        }
+
+        clear_background(LIGHTGRAY);
+        draw_text("Profiles", 40., 50., TEXT_SIZE * 1.5, BLACK);
+        let active = storage::get::<Profile>().clone();
+        for (i, profile) in profiles.iter().enumerate() {
+            let is_active = profile.id() == active.id();
+            let txt = format!(
+                "{}{}{}",
+                if i == selected { "> " } else { "" },
+                profile.name,
+                if is_active { " (active)" } else { "" },
+            );
+            let y = 90. + LINE_HEIGHT * i as f32;
+            draw_text(&txt, 40., y, TEXT_SIZE, if i == selected { RED } else { BLACK });
+        }
+        let new_row = match &new_name {
+            Some(name) => format!("> Name: {}_", name),
+            None if selected == profiles.len() => "> New profile".to_string(),
+            None => "New profile".to_string(),
+        };
+        let y = 90. + LINE_HEIGHT * profiles.len() as f32;
+        draw_text(&new_row, 40., y, TEXT_SIZE, if selected == profiles.len() { RED } else { BLACK });
+
+        let x = screen_width() / 2. + 40.;
+        let stats = &active.stats;
+        let lines = [
+            format!("Playing as {}", active.name),
+            format!("Matches: {} won of {}", stats.matches_won, stats.matches_played),
+            format!("Rounds: {} won, {} lost", stats.rounds_won, stats.rounds_lost),
+            format!("Time played: {} min", (stats.time_played / 60.) as u32),
+            format!("Characters unlocked: {}", active.campaign.unlocked_characters.len()),
+            String::new(),
+            "Key bindings:".to_string(),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, x, 90. + LINE_HEIGHT * i as f32, TEXT_SIZE, BLACK);
+        }
+        for (i, action) in KeyBindings::ACTIONS.iter().enumerate() {
+            let txt = if binding == Some(i) {
+                format!("{:?}: press a key", action)
+            } else {
+                format!("{:?}: {}", action, active.key_bindings.binding(action))
+            };
+            let y = 90. + LINE_HEIGHT * (lines.len() + i) as f32;
+            draw_text(&txt, x + 20., y, TEXT_SIZE, if binding == Some(i) { RED } else { BLACK });
+        }
+
+        let help = "Up/Down: profile  Enter: play as / new  K: bind keys of the active profile  Escape: back";
+        draw_text(help, 40., screen_height() - 30., TEXT_SIZE * 0.75, DARKGRAY);
+        draw_text(&status, 40., screen_height() - 60., TEXT_SIZE * 0.75, BLACK);
+        next_frame().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_campaign_progress() {
+        // a `campaign_progress.json` file, from before there were profiles
+        let bytes = br#"{ "team": "sharks", "stage": 3, "unlocked_characters": ["sharky_captain"] }"#;
+        let profile = Profile::from_bytes(bytes).unwrap();
+        assert_eq!(profile.version, Profile::VERSION);
+        assert_eq!(profile.name, Profile::DEFAULT_NAME);
+        assert_eq!(profile.campaign.team.as_deref(), Some("sharks"));
+        assert_eq!(profile.campaign.stage, 3);
+        assert_eq!(profile.campaign.unlocked_characters, ["sharky_captain"]);
+        assert_eq!(profile.key_bindings.up, KeyBindings::default().up);
+        assert_eq!(profile.stats.matches_played, 0);
+    }
+
+    #[test]
+    fn test_reject_newer_version() {
+        let bytes = format!(r#"{{ "version": {}, "name": "Future" }}"#, Profile::VERSION + 1);
+        let err = Profile::from_bytes(bytes.as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Parsing);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut profile = Profile::new("Round Trip");
+        profile.key_bindings.bind(&PlayerAction::B, KeyCode::Space);
+        profile.campaign.stage = 2;
+        profile.campaign.unlocked_characters.push("bandit_chief".to_string());
+        profile.stats.matches_won = 4;
+        profile.stats.time_played = 321.5;
+
+        let bytes = serialize_json_bytes(&profile).unwrap();
+        let loaded = Profile::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.version, Profile::VERSION);
+        assert_eq!(loaded.name, "Round Trip");
+        assert_eq!(loaded.key_bindings.key(&PlayerAction::B), KeyCode::Space);
+        assert_eq!(loaded.campaign.stage, 2);
+        assert_eq!(loaded.campaign.unlocked_characters, ["bandit_chief"]);
+        assert_eq!(loaded.stats.matches_won, 4);
+        assert_eq!(loaded.stats.time_played, 321.5);
+    }
+}
@@ -1,14 +1,20 @@
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use macroquad::prelude::*;
 use macroquad::prelude::collections::storage;
 use macroquad::prelude::coroutines::start_coroutine;
 use macroquad::texture;
 use crate::helpers::text::ToStringHelper;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use crate::error::{Result, Error};
+use crate::game::audio::SoundParams;
 use crate::game::ball::BallParams;
+use crate::game::campaign::CampaignStageParams;
+use crate::game::court::CourtParams;
+use crate::game::team::{load_custom_teams, merge_teams, TeamParams};
 use crate::game::character::PlayerCharacterParams;
+use crate::game::particles::ParticleEmitterMetadata;
 use crate::json::deserialize_json_file;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -48,6 +54,13 @@ pub struct Resources {
     pub textures: HashMap<String, TextureResource>,
     pub player_characters: Vec<PlayerCharacterParams>,
     pub balls: Vec<BallParams>,
+    pub teams: Vec<TeamParams>,
+    pub courts: Vec<CourtParams>,
+    /// The stages of the campaign, in the order they are played
+    pub campaign: Vec<CampaignStageParams>,
+    pub particle_effects: HashMap<String, ParticleEmitterMetadata>,
+    /// The sounds that can be played. The files are loaded by `load_audio`
+    pub sounds: Vec<SoundParams>,
 }
 
 impl Resources {
@@ -55,63 +68,132 @@ impl Resources {
     pub const RESOURCE_FILES_EXTENSION: &'static str = "json";
     pub const PLAYER_CHARACTERS_FILE: &'static str = "player_characters";
     pub const BALLS_FILE: &'static str = "balls";
+    pub const TEAMS_FILE: &'static str = "teams";
+    pub const COURTS_FILE: &'static str = "courts";
+    pub const CAMPAIGN_FILE: &'static str = "campaign";
+    pub const PARTICLE_EFFECTS_FILE: &'static str = "particle_effects";
+    pub const SOUNDS_FILE: &'static str = "sounds";
+
+    /// Every file that resources are loaded from, without the extension
+    pub const FILES: [&'static str; 8] = [
+        Self::TEXTURES_FILE,
+        Self::PLAYER_CHARACTERS_FILE,
+        Self::BALLS_FILE,
+        Self::TEAMS_FILE,
+        Self::COURTS_FILE,
+        Self::CAMPAIGN_FILE,
+        Self::PARTICLE_EFFECTS_FILE,
+        Self::SOUNDS_FILE,
+    ];
 
     pub async fn new(assets_dir: &str) -> Result<Self> {
-        let assets_dir_path = Path::new(assets_dir);
-
-        let textures = {
-            let mut textures = HashMap::new();
-            let textures_file_path = assets_dir_path
-                .join(Self::TEXTURES_FILE)
-                .with_extension(Self::RESOURCE_FILES_EXTENSION);
-            let metadata: Vec<TextureMetadata> = deserialize_json_file(&textures_file_path).await?;
-            for meta in metadata {
-                let file_path = assets_dir_path.join(&meta.path);
-                let texture = load_texture(&file_path.to_string_helper()).await?;
-                texture.set_filter(meta.filter_mode);
-                let sprite_size = {
-                    let val = meta
-                        .sprite_size
-                        .unwrap_or_else(|| vec2(texture.width(), texture.height()).as_u32());
-
-                    Some(val)
-                };
-                let size = vec2(texture.width(), texture.height());
-                let key = meta.id.clone();
-                let meta = TextureMetadata {
-                    sprite_size,
-                    size,
-                    ..meta
-                };
-                let res = TextureResource { texture, meta };
-                textures.insert(key, res);
-            }
-            textures
-        };
+        Ok(Resources {
+            assets_dir: assets_dir.to_string(),
+            textures: load_textures(assets_dir).await?,
+            player_characters: load_resource_file(assets_dir, Self::PLAYER_CHARACTERS_FILE).await?,
+            balls: load_resource_file(assets_dir, Self::BALLS_FILE).await?,
+            teams: load_teams(assets_dir).await?,
+            courts: load_resource_file(assets_dir, Self::COURTS_FILE).await?,
+            campaign: load_resource_file(assets_dir, Self::CAMPAIGN_FILE).await?,
+            particle_effects: load_particle_effects(assets_dir).await?,
+            sounds: load_resource_file(assets_dir, Self::SOUNDS_FILE).await?,
+        })
+    }
 
-        let player_characters = {
-            let path = assets_dir_path
-                .join(Self::PLAYER_CHARACTERS_FILE)
-                .with_extension(Self::RESOURCE_FILES_EXTENSION);
+    /// The path of one of `FILES`
+    pub fn file_path(assets_dir: &str, file: &str) -> PathBuf {
+        Path::new(assets_dir)
+            .join(file)
+            .with_extension(Self::RESOURCE_FILES_EXTENSION)
+    }
+}
 
-            deserialize_json_file(&path).await?
-        };
+async fn load_resource_file<T: DeserializeOwned>(assets_dir: &str, file: &str) -> Result<T> {
+    deserialize_json_file(&Resources::file_path(assets_dir, file)).await
+}
+
+/// The shipped teams, with the ones saved from the team builder put over them
+async fn load_teams(assets_dir: &str) -> Result<Vec<TeamParams>> {
+    let teams = load_resource_file(assets_dir, Resources::TEAMS_FILE).await?;
+    Ok(merge_teams(teams, load_custom_teams()?))
+}
 
-        let balls = {
-            let path = assets_dir_path
-                .join(Self::BALLS_FILE)
-                .with_extension(Self::RESOURCE_FILES_EXTENSION);
-            deserialize_json_file(&path).await?
+async fn load_textures(assets_dir: &str) -> Result<HashMap<String, TextureResource>> {
+    let mut textures = HashMap::new();
+    let metadata: Vec<TextureMetadata> = load_resource_file(assets_dir, Resources::TEXTURES_FILE).await?;
+    for meta in metadata {
+        let file_path = Path::new(assets_dir).join(&meta.path);
+        let texture = load_texture(&file_path.to_string_helper()).await?;
+        texture.set_filter(meta.filter_mode);
+        let sprite_size = {
+            let val = meta
+                .sprite_size
+                .unwrap_or_else(|| vec2(texture.width(), texture.height()).as_u32());
+
+            Some(val)
+        };
+        let size = vec2(texture.width(), texture.height());
+        let key = meta.id.clone();
+        let meta = TextureMetadata {
+            sprite_size,
+            size,
+            ..meta
         };
+        let res = TextureResource { texture, meta };
+        textures.insert(key, res);
+    }
+    Ok(textures)
+}
 
-        #[allow(clippy::inconsistent_struct_constructor)]
-        Ok(Resources {
-            assets_dir: assets_dir.to_string(),
-            textures,
-            player_characters,
-            balls,
-        })
+async fn load_particle_effects(assets_dir: &str) -> Result<HashMap<String, ParticleEmitterMetadata>> {
+    let metadata: Vec<ParticleEmitterMetadata> = load_resource_file(assets_dir, Resources::PARTICLE_EFFECTS_FILE).await?;
+    Ok(metadata
+        .into_iter()
+        .map(|meta| (meta.id.clone(), meta))
+        .collect())
+}
+
+/// Loads one of `Resources::FILES` again, and replaces what was loaded from it in storage. If the
+/// file can not be loaded, the resources in storage are left as they were
+pub async fn reload_resource_file(file: &str) -> Result<()> {
+    let assets_dir = storage::get::<Resources>().assets_dir.clone();
+    let assets_dir = assets_dir.as_str();
+    match file {
+        Resources::TEXTURES_FILE => {
+            let textures = load_textures(assets_dir).await?;
+            storage::get_mut::<Resources>().textures = textures;
+        }
+        Resources::PLAYER_CHARACTERS_FILE => {
+            let player_characters = load_resource_file(assets_dir, file).await?;
+            storage::get_mut::<Resources>().player_characters = player_characters;
+        }
+        Resources::BALLS_FILE => {
+            let balls = load_resource_file(assets_dir, file).await?;
+            storage::get_mut::<Resources>().balls = balls;
+        }
+        Resources::TEAMS_FILE => {
+            let teams = load_teams(assets_dir).await?;
+            storage::get_mut::<Resources>().teams = teams;
+        }
+        Resources::COURTS_FILE => {
+            let courts = load_resource_file(assets_dir, file).await?;
+            storage::get_mut::<Resources>().courts = courts;
+        }
+        Resources::CAMPAIGN_FILE => {
+            let campaign = load_resource_file(assets_dir, file).await?;
+            storage::get_mut::<Resources>().campaign = campaign;
+        }
+        Resources::PARTICLE_EFFECTS_FILE => {
+            let particle_effects = load_particle_effects(assets_dir).await?;
+            storage::get_mut::<Resources>().particle_effects = particle_effects;
+        }
+        Resources::SOUNDS_FILE => {
+            let sounds = load_resource_file(assets_dir, file).await?;
+            storage::get_mut::<Resources>().sounds = sounds;
+        }
+        _ => {}
     }
+    Ok(())
 }
 
 pub async fn load_resources(assets_dir: &str) {
@@ -0,0 +1,303 @@
+//! This implements the flow between the screens of the game. Every `Scene` is an async function
+//! that runs until the player leaves it, and returns a `Transition`, telling the `SceneManager`
+//! what to show next. Scenes are kept on a stack, so that Escape always takes the player back to
+//! the screen they came from.
+
+use macroquad::prelude::collections::storage;
+use macroquad::prelude::*;
+
+use crate::game::ai::Difficulty;
+use crate::game::campaign::campaign;
+use crate::game::character_select::character_select;
+use crate::game::match_options::{Controller, MatchOptions};
+use crate::game::match_state::{MatchEnd, MatchResult};
+use crate::game::profile::profiles_menu;
+use crate::game::resources::Resources;
+use crate::game::settings::settings_menu;
+use crate::game::stats::results_screen;
+use crate::game::team_builder::team_builder;
+use crate::game::tournament::tournament;
+use crate::Team;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Scene {
+    Title,
+    MainMenu,
+    ModeSelect,
+    CharacterSelect,
+    /// The world tour
+    Campaign,
+    Tournament,
+    TeamBuilder,
+    Profiles,
+    Settings,
+    Match,
+    Results,
+    Credits,
+    /// Shows off the animations of every character and ball
+    Gallery,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Transition {
+    /// Shows a scene on top of the current one, which is returned to when it is left
+    Push(Scene),
+    /// Replaces the current scene, so that leaving the new one returns to the scene before it
+    Replace(Scene),
+    /// Goes back to the previous scene
+    Pop,
+    /// Goes back until the given scene is reached
+    PopTo(Scene),
+    Quit,
+}
+
+pub struct SceneManager {
+    stack: Vec<Scene>,
+    options: MatchOptions,
+    /// How the last match went, shown on the results screen
+    result: Option<MatchResult>,
+}
+
+impl SceneManager {
+    const TITLE_SIZE: f32 = 48.;
+    const TEXT_SIZE: f32 = 28.;
+    const LINE_HEIGHT: f32 = 40.;
+
+    pub fn new() -> Self {
+        SceneManager {
+            stack: vec![Scene::Title],
+            options: MatchOptions::default(),
+            result: None,
+        }
+    }
+
+    /// Runs scenes until the player quits, or leaves the last scene
+    pub async fn run(&mut self) {
+        while let Some(scene) = self.stack.last().copied() {
+            let transition = self.run_scene(scene).await;
+            match transition {
+                Transition::Push(scene) => self.stack.push(scene),
+                Transition::Replace(scene) => {
+                    self.stack.pop();
+                    self.stack.push(scene);
+                }
+                Transition::Pop => {
+                    self.stack.pop();
+                }
+                Transition::PopTo(scene) => {
+                    while self.stack.last().is_some_and(|top| *top != scene) {
+                        self.stack.pop();
+                    }
+                }
+                Transition::Quit => self.stack.clear(),
+            }
+            // the key that caused the transition should not also be read by the next scene
+            next_frame().await;
+        }
+    }
+
+    async fn run_scene(&mut self, scene: Scene) -> Transition {
+        match scene {
+            Scene::Title => title().await,
+            Scene::MainMenu => {
+                let items = ["Play", "World tour", "Tournament", "Team builder", "Profiles", "Settings", "Gallery", "Credits", "Quit"];
+                match menu("Super dodge ball", &items).await {
+                    Some(0) => Transition::Push(Scene::ModeSelect),
+                    Some(1) => Transition::Push(Scene::Campaign),
+                    Some(2) => Transition::Push(Scene::Tournament),
+                    Some(3) => Transition::Push(Scene::TeamBuilder),
+                    Some(4) => Transition::Push(Scene::Profiles),
+                    Some(5) => Transition::Push(Scene::Settings),
+                    Some(6) => Transition::Push(Scene::Gallery),
+                    Some(7) => Transition::Push(Scene::Credits),
+                    Some(_) => Transition::Quit,
+                    None => Transition::Pop,
+                }
+            }
+            Scene::ModeSelect => {
+                let modes = [
+                    ("Versus CPU (easy)", Controller::Human, Controller::Cpu(Difficulty::Easy)),
+                    ("Versus CPU (normal)", Controller::Human, Controller::Cpu(Difficulty::Normal)),
+                    ("Versus CPU (hard)", Controller::Human, Controller::Cpu(Difficulty::Hard)),
+                    ("Two players", Controller::Human, Controller::Human),
+                    ("Watch CPU vs CPU", Controller::Cpu(Difficulty::Normal), Controller::Cpu(Difficulty::Normal)),
+                ];
+                let items: Vec<&str> = modes.iter().map(|(label, _, _)| *label).collect();
+                match menu("Mode select", &items).await {
+                    Some(i) => {
+                        let (_, team_one, team_two) = modes[i];
+                        self.options.team_one = team_one;
+                        self.options.team_two = team_two;
+                        Transition::Push(Scene::CharacterSelect)
+                    }
+                    None => Transition::Pop,
+                }
+            }
+            Scene::CharacterSelect => {
+                if character_select(&mut self.options).await {
+                    Transition::Push(Scene::Match)
+                } else {
+                    Transition::Pop
+                }
+            }
+            Scene::Campaign => {
+                if campaign(&self.options).await {
+                    Transition::PopTo(Scene::MainMenu)
+                } else {
+                    Transition::Pop
+                }
+            }
+            Scene::Tournament => {
+                if tournament(&self.options).await {
+                    Transition::PopTo(Scene::MainMenu)
+                } else {
+                    Transition::Pop
+                }
+            }
+            Scene::TeamBuilder => {
+                team_builder().await;
+                Transition::Pop
+            }
+            Scene::Profiles => {
+                profiles_menu().await;
+                Transition::Pop
+            }
+            Scene::Settings => {
+                settings_menu().await;
+                Transition::Pop
+            }
+            Scene::Match => match crate::local_game(&mut self.options).await {
+                MatchEnd::Finished(result) => {
+                    self.result = Some(result);
+                    Transition::Replace(Scene::Results)
+                }
+                MatchEnd::QuitToMenu => Transition::PopTo(Scene::MainMenu),
+            },
+            Scene::Results => {
+                let result = match &self.result {
+                    Some(result) => result,
+                    None => return Transition::Pop,
+                };
+                let title = format!("{} wins!", self.team_name(result.winner));
+                let items = ["Rematch", "Character select", "Main menu"];
+                match results_screen(&title, result, &items).await {
+                    Some(0) => Transition::Replace(Scene::Match),
+                    Some(1) | None => Transition::Pop,
+                    Some(_) => Transition::PopTo(Scene::MainMenu),
+                }
+            }
+            Scene::Credits => credits().await,
+            Scene::Gallery => {
+                crate::player_animation_demo().await;
+                Transition::Pop
+            }
+        }
+    }
+}
+
+impl SceneManager {
+    /// The name of the team picked for a side, falling back to "Team one" or "Team two"
+    fn team_name(&self, team: Team) -> String {
+        let resources = storage::get::<Resources>();
+        let params = self.options.team_id(team)
+            .and_then(|id| resources.teams.iter().find(|params| params.id == id));
+        match (params, team) {
+            (Some(params), _) => params.name.clone(),
+            (None, Team::One) => "Team one".to_string(),
+            (None, Team::Two) => "Team two".to_string(),
+        }
+    }
+}
+
+impl Default for SceneManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn title() -> Transition {
+    loop {
+        if is_key_pressed(KeyCode::Escape) {
+            return Transition::Pop;
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
+            return Transition::Push(Scene::MainMenu);
+        }
+        clear_background(LIGHTGRAY);
+        draw_centered_text("Super dodge ball", screen_height() / 2. - 40., SceneManager::TITLE_SIZE * 1.5, BLACK);
+        // blink the prompt, about once a second
+        if get_time().fract() < 0.6 {
+            draw_centered_text("Press Enter", screen_height() / 2. + 40., SceneManager::TEXT_SIZE, DARKGRAY);
+        }
+        next_frame().await;
+    }
+}
+
+async fn credits() -> Transition {
+    let lines = [
+        "Super dodge ball",
+        "",
+        "Made by the Fish Folk community",
+        "Art from Fish Fight",
+        "Built with macroquad",
+    ];
+    loop {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Enter) {
+            return Transition::Pop;
+        }
+        clear_background(LIGHTGRAY);
+        for (i, line) in lines.iter().enumerate() {
+            let y = 120. + SceneManager::LINE_HEIGHT * i as f32;
+            draw_centered_text(line, y, SceneManager::TEXT_SIZE, BLACK);
+        }
+        draw_help("Escape: back");
+        next_frame().await;
+    }
+}
+
+/// Shows a list of items to pick from, with the arrow keys and Enter. Returns the index of the
+/// picked item, or `None` if the menu was left with Escape.
+pub async fn menu(title: &str, items: &[&str]) -> Option<usize> {
+    let mut selected = 0;
+    loop {
+        if is_key_pressed(KeyCode::Escape) {
+            return None;
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            return Some(selected);
+        }
+        if is_key_pressed(KeyCode::Up) {
+            selected = (selected + items.len() - 1) % items.len();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            selected = (selected + 1) % items.len();
+        }
+        clear_background(LIGHTGRAY);
+        draw_menu(title, items, selected);
+        next_frame().await;
+    }
+}
+
+/// Draws a menu, without clearing the screen, so that it can be drawn on top of a paused match
+pub fn draw_menu(title: &str, items: &[&str], selected: usize) {
+    draw_centered_text(title, 120., SceneManager::TITLE_SIZE, BLACK);
+    for (i, item) in items.iter().enumerate() {
+        let y = 220. + SceneManager::LINE_HEIGHT * i as f32;
+        let (txt, color) = if i == selected {
+            (format!("> {} <", item), RED)
+        } else {
+            (item.to_string(), BLACK)
+        };
+        draw_centered_text(&txt, y, SceneManager::TEXT_SIZE, color);
+    }
+    draw_help("Up/Down: select  Enter: confirm  Escape: back");
+}
+
+fn draw_help(txt: &str) {
+    draw_text(txt, 40., screen_height() - 30., 18., DARKGRAY);
+}
+
+fn draw_centered_text(txt: &str, y: f32, size: f32, color: Color) {
+    let dimensions = measure_text(txt, None, size as u16, 1.);
+    draw_text(txt, (screen_width() - dimensions.width) / 2., y, size, color);
+}
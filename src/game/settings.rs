@@ -0,0 +1,219 @@
+//! This implements `Settings`, which holds the player's preferences. They are saved to the
+//! `settings.json` file in the data directory, as the assets directory may not be writable. Until
+//! they are first saved, the `settings.json` file in the assets directory is used, and if that is
+//! missing as well, or can not be parsed, the defaults will be used.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use macroquad::prelude::collections::storage;
+use macroquad::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::game::audio::Audio;
+use crate::game::profile::data_dir;
+use crate::game::resources::Resources;
+use crate::game::scene::draw_menu;
+use crate::json::{deserialize_json_bytes, deserialize_json_file, serialize_json_bytes};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Settings {
+    /// If this is false, the camera will not shake when a ball hits a player
+    #[serde(default = "Settings::default_screen_shake")]
+    pub screen_shake: bool,
+    /// This is multiplied with the strength of every screen shake. Values below 1 will tone it down
+    #[serde(default = "Settings::default_screen_shake_scale")]
+    pub screen_shake_scale: f32,
+    /// If this is false, the game will not freeze for a moment when a ball hits a player
+    #[serde(default = "Settings::default_hit_stop")]
+    pub hit_stop: bool,
+    /// This is the amount of frames the game freezes for, on the hardest possible hit
+    #[serde(default = "Settings::default_hit_stop_frames")]
+    pub hit_stop_frames: u32,
+    /// The volume of everything, from 0 to 1
+    #[serde(default = "Settings::default_master_volume")]
+    pub master_volume: f32,
+    /// The volume of the music, from 0 to 1. This is multiplied with `master_volume`
+    #[serde(default = "Settings::default_music_volume")]
+    pub music_volume: f32,
+    /// The volume of sound effects, from 0 to 1. This is multiplied with `master_volume`
+    #[serde(default = "Settings::default_effects_volume")]
+    pub effects_volume: f32,
+    /// The id of the profile that is played with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+}
+
+impl Settings {
+    pub const SETTINGS_FILE: &'static str = "settings";
+
+    const DEFAULT_SCREEN_SHAKE_SCALE: f32 = 1.0;
+    const DEFAULT_HIT_STOP_FRAMES: u32 = 6;
+    const DEFAULT_MASTER_VOLUME: f32 = 1.0;
+    const DEFAULT_MUSIC_VOLUME: f32 = 0.6;
+    const DEFAULT_EFFECTS_VOLUME: f32 = 1.0;
+
+    const MAX_SCREEN_SHAKE_SCALE: f32 = 2.0;
+    const SCREEN_SHAKE_SCALE_STEP: f32 = 0.25;
+    const MAX_HIT_STOP_FRAMES: u32 = 12;
+    const VOLUME_STEP: f32 = 0.1;
+
+    pub fn default_screen_shake() -> bool {
+        true
+    }
+
+    pub fn default_screen_shake_scale() -> f32 {
+        Self::DEFAULT_SCREEN_SHAKE_SCALE
+    }
+
+    pub fn default_hit_stop() -> bool {
+        true
+    }
+
+    pub fn default_hit_stop_frames() -> u32 {
+        Self::DEFAULT_HIT_STOP_FRAMES
+    }
+
+    pub fn default_master_volume() -> f32 {
+        Self::DEFAULT_MASTER_VOLUME
+    }
+
+    pub fn default_music_volume() -> f32 {
+        Self::DEFAULT_MUSIC_VOLUME
+    }
+
+    pub fn default_effects_volume() -> f32 {
+        Self::DEFAULT_EFFECTS_VOLUME
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            screen_shake: Self::default_screen_shake(),
+            screen_shake_scale: Self::default_screen_shake_scale(),
+            hit_stop: Self::default_hit_stop(),
+            hit_stop_frames: Self::default_hit_stop_frames(),
+            master_volume: Self::default_master_volume(),
+            music_volume: Self::default_music_volume(),
+            effects_volume: Self::default_effects_volume(),
+            profile: None,
+        }
+    }
+}
+
+fn settings_path() -> PathBuf {
+    data_dir()
+        .join(Settings::SETTINGS_FILE)
+        .with_extension(Resources::RESOURCE_FILES_EXTENSION)
+}
+
+/// Loads the saved settings, falling back to the ones in `assets_dir`, and stores them
+pub async fn load_settings(assets_dir: &str) {
+    let saved = fs::read(settings_path())
+        .ok()
+        .and_then(|bytes| deserialize_json_bytes(&bytes).ok());
+    let settings: Settings = match saved {
+        Some(settings) => settings,
+        None => {
+            let path = Path::new(assets_dir)
+                .join(Settings::SETTINGS_FILE)
+                .with_extension(Resources::RESOURCE_FILES_EXTENSION);
+            deserialize_json_file(&path).await.unwrap_or_default()
+        }
+    };
+    storage::store(settings);
+}
+
+/// Writes `settings` to the `settings.json` file in the data directory
+pub fn save_settings(settings: &Settings) -> Result<()> {
+    fs::create_dir_all(data_dir())?;
+    let bytes = serialize_json_bytes(settings)?;
+    fs::write(settings_path(), bytes)?;
+    Ok(())
+}
+
+/// Shows the settings screen, until it is left with Escape. Changes take effect right away, and
+/// are saved when the screen is left. If they can not be saved, the error is shown, and the screen
+/// is left without saving on the next Escape
+pub async fn settings_menu() {
+    let mut selected = 0;
+    let rows = 7;
+    let mut status = String::new();
+    loop {
+        if is_key_pressed(KeyCode::Escape) {
+            if !status.is_empty() {
+                return;
+            }
+            match save_settings(&storage::get::<Settings>()) {
+                Ok(()) => return,
+                Err(err) => status = format!("Could not save the settings: {}. Escape: leave anyway", err),
+            }
+        }
+        if is_key_pressed(KeyCode::Up) {
+            selected = (selected + rows - 1) % rows;
+        }
+        if is_key_pressed(KeyCode::Down) {
+            selected = (selected + 1) % rows;
+        }
+        let step = if is_key_pressed(KeyCode::Left) {
+            -1
+        } else if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::Enter) {
+            1
+        } else {
+            0
+        };
+        let items = {
+            let mut settings = storage::get_mut::<Settings>();
+            if step != 0 {
+                match selected {
+                    0 => settings.screen_shake = !settings.screen_shake,
+                    1 => {
+                        let scale = settings.screen_shake_scale + Settings::SCREEN_SHAKE_SCALE_STEP * step as f32;
+                        settings.screen_shake_scale = scale.clamp(0., Settings::MAX_SCREEN_SHAKE_SCALE);
+                    }
+                    2 => settings.hit_stop = !settings.hit_stop,
+                    3 => {
+                        let frames = settings.hit_stop_frames as i32 + step;
+                        settings.hit_stop_frames = frames.clamp(0, Settings::MAX_HIT_STOP_FRAMES as i32) as u32;
+                    }
+                    4 => settings.master_volume = step_volume(settings.master_volume, step),
+                    5 => settings.music_volume = step_volume(settings.music_volume, step),
+                    _ => settings.effects_volume = step_volume(settings.effects_volume, step),
+                }
+            }
+            [
+                format!("Screen shake: {}", if settings.screen_shake { "on" } else { "off" }),
+                format!("Screen shake strength: {:.2}", settings.screen_shake_scale),
+                format!("Hit stop: {}", if settings.hit_stop { "on" } else { "off" }),
+                format!("Hit stop frames: {}", settings.hit_stop_frames),
+                format!("Master volume: {:.0}%", settings.master_volume * 100.),
+                format!("Music volume: {:.0}%", settings.music_volume * 100.),
+                format!("Effects volume: {:.0}%", settings.effects_volume * 100.),
+            ]
+        };
+        if step != 0 {
+            if let Some(mut audio) = storage::try_get_mut::<Audio>() {
+                audio.set_settings(&storage::get::<Settings>());
+                // lets the player hear the new volume
+                if selected == 6 {
+                    audio.play(Audio::CATCH_SOUND_ID);
+                }
+            }
+        }
+        clear_background(LIGHTGRAY);
+        let items: Vec<&str> = items.iter().map(String::as_str).collect();
+        draw_menu("Settings", &items, selected);
+        draw_text("Left/Right: change", 40., screen_height() - 55., 18., DARKGRAY);
+        draw_text(&status, 40., screen_height() - 80., 18., RED);
+        next_frame().await;
+    }
+}
+
+fn step_volume(volume: f32, step: i32) -> f32 {
+    // rounded, so that steps do not drift away from whole percentages
+    let volume = volume + Settings::VOLUME_STEP * step as f32;
+    (volume.clamp(0., 1.) * 100.).round() / 100.
+}
@@ -0,0 +1,280 @@
+//! This implements `PlayerStats`, which counts what every player did during a match. `Game` keeps
+//! the stats of the round that is being played, counting them from its events, and `MatchState`
+//! adds them up over every round.
+//!
+//! Once a match is over, the stats are shown on the results screen, along with the MVP, and can
+//! be exported as JSON and as CSV, which spreadsheets can open.
+
+use std::fs;
+use std::path::PathBuf;
+
+use macroquad::prelude::*;
+
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::game::events::{GameEvent, GameEventListener};
+use crate::game::match_state::MatchResult;
+use crate::game::profile::data_dir;
+use crate::game::scene::draw_menu;
+use crate::json::serialize_json_bytes;
+
+/// The directory, in the data directory, that stats are exported to
+const EXPORTS_DIR: &str = "exports";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlayerStats {
+    /// The name of the player's character
+    pub name: String,
+    /// The name of the player's team
+    pub team: String,
+    /// Throws at the other team. Passes are not counted
+    pub throws: u32,
+    /// Throws that hit a player of the other team
+    pub hits: u32,
+    /// Balls thrown by the other team, that were caught
+    pub catches: u32,
+    /// Balls that hit the player while they were trying to catch them, including passes
+    pub fumbles: u32,
+    /// Balls thrown by the other team, that were ducked under
+    pub dodges: u32,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    /// The time, in seconds, the player was in play, leaving out the rounds after they were out
+    pub time_alive: f32,
+}
+
+impl PlayerStats {
+    const HIT_SCORE: f32 = 3.;
+    const CATCH_SCORE: f32 = 4.;
+    const DODGE_SCORE: f32 = 1.;
+    const FUMBLE_SCORE: f32 = -2.;
+    /// This is multiplied with the damage dealt
+    const DAMAGE_SCORE: f32 = 0.1;
+
+    pub fn new(name: &str, team: &str) -> Self {
+        PlayerStats {
+            name: name.to_string(),
+            team: team.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds the stats of another round
+    pub fn add(&mut self, other: &PlayerStats) {
+        self.throws += other.throws;
+        self.hits += other.hits;
+        self.catches += other.catches;
+        self.fumbles += other.fumbles;
+        self.dodges += other.dodges;
+        self.damage_dealt += other.damage_dealt;
+        self.damage_taken += other.damage_taken;
+        self.time_alive += other.time_alive;
+    }
+
+    /// How much the player did for their team, which is what the MVP is picked by
+    pub fn score(&self) -> f32 {
+        self.hits as f32 * Self::HIT_SCORE
+            + self.catches as f32 * Self::CATCH_SCORE
+            + self.dodges as f32 * Self::DODGE_SCORE
+            + self.fumbles as f32 * Self::FUMBLE_SCORE
+            + self.damage_dealt as f32 * Self::DAMAGE_SCORE
+    }
+}
+
+/// The stats of a round, by player index, are counted from the events of the game
+impl GameEventListener for Vec<PlayerStats> {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::BallThrown { thrower, .. } => self[thrower].throws += 1,
+            GameEvent::BallCaught { player, from_opponent: true, .. } => self[player].catches += 1,
+            GameEvent::BallFumbled { player, .. } => self[player].fumbles += 1,
+            GameEvent::BallDodged { player, .. } => self[player].dodges += 1,
+            GameEvent::PlayerHit { player, thrower, damage, .. } => {
+                self[player].damage_taken += damage;
+                if let Some(thrower) = thrower {
+                    self[thrower].hits += 1;
+                    self[thrower].damage_dealt += damage;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The index of the player with the highest score
+pub fn mvp(stats: &[PlayerStats]) -> Option<usize> {
+    (0..stats.len()).max_by(|&a, &b| stats[a].score().partial_cmp(&stats[b].score()).unwrap())
+}
+
+/// Writes the stats as CSV, with a header row, and a row for every player
+pub fn stats_to_csv(stats: &[PlayerStats]) -> String {
+    let mut csv = "player,team,throws,hits,catches,fumbles,dodges,damage_dealt,damage_taken,time_alive\n".to_string();
+    for player in stats {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{:.1}\n",
+            csv_field(&player.name),
+            csv_field(&player.team),
+            player.throws,
+            player.hits,
+            player.catches,
+            player.fumbles,
+            player.dodges,
+            player.damage_dealt,
+            player.damage_taken,
+            player.time_alive,
+        ));
+    }
+    csv
+}
+
+/// Quotes a CSV field, if it has characters that would break the row up
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Exports the stats of a match as both a JSON and a CSV file, in the exports directory, named
+/// after the time of the export. Returns the path of the files, without an extension
+pub fn export_stats(stats: &[PlayerStats]) -> Result<PathBuf> {
+    let dir = data_dir().join(EXPORTS_DIR);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("match-{}", miniquad::date::now() as u64));
+    fs::write(path.with_extension("json"), serialize_json_bytes(&stats)?)?;
+    fs::write(path.with_extension("csv"), stats_to_csv(stats))?;
+    Ok(path)
+}
+
+const TEXT_SIZE: f32 = 20.;
+const LINE_HEIGHT: f32 = 24.;
+const EXPORT_KEY: KeyCode = KeyCode::E;
+
+/// The columns of the stats table, with their headers and x positions
+const COLUMNS: [(&str, f32); 10] = [
+    ("Player", 40.),
+    ("Team", 200.),
+    ("Throws", 360.),
+    ("Hits", 430.),
+    ("Catches", 490.),
+    ("Fumbles", 570.),
+    ("Dodges", 650.),
+    ("Dealt", 720.),
+    ("Taken", 780.),
+    ("Time", 840.),
+];
+
+/// Shows the results of a match, with the stats of every player, and a menu to pick what to do
+/// next. Returns the index of the picked item, or `None` if the screen was left with Escape
+pub async fn results_screen(title: &str, result: &MatchResult, items: &[&str]) -> Option<usize> {
+    let mut selected = 0;
+    let mvp = mvp(&result.stats);
+    let mut status = format!("{:?}: export the stats", EXPORT_KEY);
+    loop {
+        if is_key_pressed(KeyCode::Escape) {
+            return None;
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            return Some(selected);
+        }
+        if is_key_pressed(KeyCode::Up) {
+            selected = (selected + items.len() - 1) % items.len();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            selected = (selected + 1) % items.len();
+        }
+        if is_key_pressed(EXPORT_KEY) {
+            status = match export_stats(&result.stats) {
+                Ok(path) => format!("Exported to {}.json and .csv", path.display()),
+                Err(err) => format!("Could not export the stats: {}", err),
+            };
+        }
+
+        clear_background(LIGHTGRAY);
+        draw_menu(title, items, selected);
+        let top = 240. + LINE_HEIGHT * (items.len() + 1) as f32;
+        for (header, x) in COLUMNS {
+            draw_text(header, x, top, TEXT_SIZE, DARKGRAY);
+        }
+        for (i, player) in result.stats.iter().enumerate() {
+            let values = [
+                player.name.clone(),
+                player.team.clone(),
+                player.throws.to_string(),
+                player.hits.to_string(),
+                player.catches.to_string(),
+                player.fumbles.to_string(),
+                player.dodges.to_string(),
+                player.damage_dealt.to_string(),
+                player.damage_taken.to_string(),
+                format!("{:.0}s", player.time_alive),
+            ];
+            let y = top + LINE_HEIGHT * (i + 1) as f32;
+            let color = if Some(i) == mvp { RED } else { BLACK };
+            for (value, (_, x)) in values.iter().zip(COLUMNS) {
+                draw_text(value, x, y, TEXT_SIZE, color);
+            }
+        }
+        if let Some(mvp) = mvp {
+            let player = &result.stats[mvp];
+            let txt = format!("MVP: {} ({})", player.name, player.team);
+            let y = top + LINE_HEIGHT * (result.stats.len() + 2) as f32;
+            draw_text(&txt, 40., y, TEXT_SIZE * 1.25, RED);
+        }
+        draw_text(&status, 40., screen_height() - 55., 18., DARKGRAY);
+        next_frame().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mvp() {
+        assert_eq!(mvp(&[]), None);
+        let mut thrower = PlayerStats::new("Thrower", "Sharks");
+        thrower.hits = 2;
+        thrower.damage_dealt = 40;
+        let mut catcher = PlayerStats::new("Catcher", "Bandits");
+        catcher.catches = 2;
+        catcher.fumbles = 1;
+        let idle = PlayerStats::new("Idle", "Bandits");
+        // 2 hits and 40 damage make 10, 2 catches and a fumble make 6
+        let stats = [catcher, thrower, idle];
+        assert_eq!(stats[1].score(), 10.);
+        assert_eq!(stats[0].score(), 6.);
+        assert_eq!(mvp(&stats), Some(1));
+    }
+
+    #[test]
+    fn test_stats_to_csv() {
+        let mut player = PlayerStats::new("Sharky", "Sharks");
+        player.throws = 5;
+        player.hits = 2;
+        player.catches = 1;
+        player.dodges = 3;
+        player.damage_dealt = 30;
+        player.damage_taken = 10;
+        player.time_alive = 61.25;
+        let csv = stats_to_csv(&[player]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines, [
+            "player,team,throws,hits,catches,fumbles,dodges,damage_dealt,damage_taken,time_alive",
+            "Sharky,Sharks,5,2,1,0,3,30,10,61.2",
+        ]);
+    }
+
+    #[test]
+    fn test_stats_to_csv_escaping() {
+        let stats = [
+            PlayerStats::new("Bandit, the \"Chief\"", "Sharks"),
+            PlayerStats::new("Two\nLines", "Bandits"),
+        ];
+        let csv = stats_to_csv(&stats);
+        assert!(csv.contains("\n\"Bandit, the \"\"Chief\"\"\",Sharks,0,"));
+        assert!(csv.contains("\n\"Two\nLines\",Bandits,0,"));
+    }
+}
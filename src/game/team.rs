@@ -0,0 +1,170 @@
+//! This implements `TeamParams`, which is a declaration of a team, loaded from the `teams.json`
+//! file. A team has a name, the colors of its kit, an emblem and a roster, which lists the
+//! character that plays each slot, along with attributes that tweak that player's stats.
+//! Teams made or edited in the team builder are saved to `teams.json` in the data directory, and
+//! are put over the shipped teams when they are loaded.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use macroquad::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::game::profile::data_dir;
+use crate::game::resources::{Resources, TextureResource};
+use crate::json::{self, deserialize_json_bytes, serialize_json_bytes};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamParams {
+    /// This is the id of the team. This should be unique, as teams are looked up by it
+    pub id: String,
+    /// This is the name of the team, as shown in menus and in the match
+    pub name: String,
+    /// The main color of the team's kit. This is also used for the team in the UI
+    #[serde(with = "json::ColorDef")]
+    pub primary_color: Color,
+    #[serde(with = "json::ColorDef")]
+    pub secondary_color: Color,
+    /// The id of the texture used as the team's emblem
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emblem: Option<String>,
+    /// The players of the team, in roster order. Infielders come first, then outfielders
+    #[serde(default)]
+    pub roster: Vec<RosterSlotParams>,
+}
+
+/// The character that plays a roster slot, and how its stats are tweaked for this team
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RosterSlotParams {
+    /// The id of the player character, from `player_characters.json`
+    pub character: String,
+    /// The life the player starts a match with
+    #[serde(default = "RosterSlotParams::default_life")]
+    pub life: i32,
+    /// This is multiplied with the damage of the balls this player throws
+    #[serde(default = "RosterSlotParams::default_power")]
+    pub power: f32,
+    /// This is multiplied with the move speed of the character
+    #[serde(default = "RosterSlotParams::default_speed")]
+    pub speed: f32,
+}
+
+impl RosterSlotParams {
+    pub const MIN_LIFE: i32 = 50;
+    pub const MAX_LIFE: i32 = 150;
+    pub const MIN_FACTOR: f32 = 0.5;
+    pub const MAX_FACTOR: f32 = 1.5;
+
+    const DEFAULT_LIFE: i32 = 100;
+    const DEFAULT_POWER: f32 = 1.0;
+    const DEFAULT_SPEED: f32 = 1.0;
+
+    pub fn new(character: &str) -> Self {
+        RosterSlotParams {
+            character: character.to_string(),
+            life: Self::default_life(),
+            power: Self::default_power(),
+            speed: Self::default_speed(),
+        }
+    }
+
+    pub fn default_life() -> i32 {
+        Self::DEFAULT_LIFE
+    }
+
+    pub fn default_power() -> f32 {
+        Self::DEFAULT_POWER
+    }
+
+    pub fn default_speed() -> f32 {
+        Self::DEFAULT_SPEED
+    }
+}
+
+fn custom_teams_path() -> PathBuf {
+    data_dir()
+        .join(Resources::TEAMS_FILE)
+        .with_extension(Resources::RESOURCE_FILES_EXTENSION)
+}
+
+/// The teams saved from the team builder. This is empty until one is saved
+pub fn load_custom_teams() -> Result<Vec<TeamParams>> {
+    let bytes = match fs::read(custom_teams_path()) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(deserialize_json_bytes(&bytes)?)
+}
+
+/// Puts `custom` over `teams`. A custom team replaces the team with the same id, and the others
+/// are added at the end
+pub fn merge_teams(mut teams: Vec<TeamParams>, custom: Vec<TeamParams>) -> Vec<TeamParams> {
+    for team in custom {
+        match teams.iter_mut().find(|other| other.id == team.id) {
+            Some(other) => *other = team,
+            None => teams.push(team),
+        }
+    }
+    teams
+}
+
+/// Adds `team` to the teams saved from the team builder, replacing the one with the same id. The
+/// shipped `teams.json` is left as it is
+pub fn save_custom_team(team: &TeamParams) -> Result<()> {
+    let teams = merge_teams(load_custom_teams()?, vec![team.clone()]);
+    fs::create_dir_all(data_dir())?;
+    let bytes = serialize_json_bytes(&teams)?;
+    fs::write(custom_teams_path(), bytes)?;
+    Ok(())
+}
+
+/// The color a player's sprite is tinted with, to show the team kit. The team color is
+/// lightened, so that the character still shows through
+pub fn kit_tint(color: Color) -> Color {
+    Color::new(0.5 + color.r / 2., 0.5 + color.g / 2., 0.5 + color.b / 2., 1.)
+}
+
+/// Draws the emblem of a team, if it has one, scaled to `size`
+pub fn draw_emblem(team: &TeamParams, textures: &HashMap<String, TextureResource>, position: Vec2, size: f32) {
+    if let Some(texture) = team.emblem.as_ref().and_then(|id| textures.get(id)) {
+        draw_texture_ex(
+            texture.texture,
+            position.x,
+            position.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(size, size)),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn team(id: &str, name: &str) -> TeamParams {
+        TeamParams {
+            id: id.to_string(),
+            name: name.to_string(),
+            primary_color: RED,
+            secondary_color: WHITE,
+            emblem: None,
+            roster: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_merge_teams() {
+        let shipped = vec![team("reds", "Reds"), team("blues", "Blues")];
+        let custom = vec![team("blues", "Better blues"), team("greens", "Greens")];
+        let names: Vec<String> = merge_teams(shipped, custom).into_iter().map(|team| team.name).collect();
+        assert_eq!(names, ["Reds", "Better blues", "Greens"]);
+    }
+}
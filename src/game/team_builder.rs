@@ -0,0 +1,290 @@
+//! This implements the team builder, where the teams in `teams.json` can be edited and new teams
+//! can be made. Saving writes the team to the custom teams in the data directory, and updates
+//! `Resources`, so that the changes show up in character select right away.
+
+use macroquad::prelude::collections::storage;
+use macroquad::prelude::*;
+
+use crate::game::animations::{AnimationParams, AnimationPlayer};
+use crate::game::campaign::available_characters;
+use crate::game::resources::Resources;
+use crate::game::team::{draw_emblem, kit_tint, save_custom_team, RosterSlotParams, TeamParams};
+use crate::{Player, INFIELDERS_PER_TEAM, OUTFIELDERS_PER_TEAM};
+
+/// Textures with an id that starts with this can be picked as an emblem
+const EMBLEM_PREFIX: &str = "emblem_";
+const MAX_NAME_LENGTH: usize = 20;
+
+const PALETTE: [Color; 16] = [
+    RED, ORANGE, GOLD, YELLOW, LIME, GREEN, DARKGREEN, SKYBLUE, BLUE, DARKBLUE, PURPLE, PINK,
+    MAROON, BROWN, WHITE, BLACK,
+];
+
+const TEXT_SIZE: f32 = 24.;
+const LINE_HEIGHT: f32 = 28.;
+
+const LIFE_STEP: i32 = 10;
+const FACTOR_STEP: f32 = 0.05;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Row {
+    /// Picks which team is edited, or a new one
+    Team,
+    Name,
+    PrimaryColor,
+    SecondaryColor,
+    Emblem,
+    Character(usize),
+    Life(usize),
+    Power(usize),
+    Speed(usize),
+}
+
+/// Shows the team builder, until it is left with Escape
+pub async fn team_builder() {
+    let (characters, mut teams, emblems) = {
+        let resources = storage::get::<Resources>();
+        let mut emblems: Vec<String> = resources
+            .textures
+            .keys()
+            .filter(|id| id.starts_with(EMBLEM_PREFIX))
+            .cloned()
+            .collect();
+        emblems.sort();
+        (
+            available_characters(&resources.player_characters),
+            resources.teams.clone(),
+            emblems,
+        )
+    };
+    if characters.is_empty() {
+        return;
+    }
+    let roster_size = INFIELDERS_PER_TEAM + OUTFIELDERS_PER_TEAM;
+    let mut rows = vec![Row::Team, Row::Name, Row::PrimaryColor, Row::SecondaryColor, Row::Emblem];
+    for slot in 0..roster_size {
+        rows.extend([Row::Character(slot), Row::Life(slot), Row::Power(slot), Row::Speed(slot)]);
+    }
+    let mut selected_row = 0;
+    // `teams.len()` stands for a new team
+    let mut current = 0;
+    let mut draft = load_draft(&teams, current, &characters[0].id, roster_size);
+    let mut status = String::new();
+    let mut previews: Vec<AnimationPlayer> = characters
+        .iter()
+        .map(|character| {
+            let animation_params: AnimationParams = character.animation.clone().into();
+            let mut animation_player = AnimationPlayer::new(animation_params);
+            animation_player.set_animation(Player::IDLE_ANIMATION_ID);
+            animation_player.set_scale(2.);
+            animation_player
+        })
+        .collect();
+    loop {
+        if is_key_pressed(KeyCode::Escape) {
+            return;
+        }
+        if is_key_pressed(KeyCode::Up) {
+            selected_row = (selected_row + rows.len() - 1) % rows.len();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            selected_row = (selected_row + 1) % rows.len();
+        }
+        let step = if is_key_pressed(KeyCode::Left) {
+            -1
+        } else if is_key_pressed(KeyCode::Right) {
+            1
+        } else {
+            0
+        };
+        let row = rows[selected_row];
+        if row == Row::Name {
+            while let Some(character) = get_char_pressed() {
+                if !character.is_control() && draft.name.chars().count() < MAX_NAME_LENGTH {
+                    draft.name.push(character);
+                }
+            }
+            if is_key_pressed(KeyCode::Backspace) {
+                draft.name.pop();
+            }
+        } else {
+            // typed characters are queued up, so they are thrown away when not editing the name
+            while get_char_pressed().is_some() {}
+        }
+        if step != 0 {
+            match row {
+                Row::Team => {
+                    current = cycle(current, teams.len() + 1, step);
+                    draft = load_draft(&teams, current, &characters[0].id, roster_size);
+                    status.clear();
+                }
+                Row::Name => {}
+                Row::PrimaryColor => draft.primary_color = cycle_color(draft.primary_color, step),
+                Row::SecondaryColor => draft.secondary_color = cycle_color(draft.secondary_color, step),
+                Row::Emblem => {
+                    // the first choice is no emblem
+                    let index = draft
+                        .emblem
+                        .as_ref()
+                        .and_then(|id| emblems.iter().position(|emblem| emblem == id))
+                        .map_or(0, |i| i + 1);
+                    let index = cycle(index, emblems.len() + 1, step);
+                    draft.emblem = index.checked_sub(1).map(|i| emblems[i].clone());
+                }
+                Row::Character(slot) => {
+                    let slot = &mut draft.roster[slot];
+                    let index = characters
+                        .iter()
+                        .position(|character| character.id == slot.character)
+                        .unwrap_or(0);
+                    slot.character = characters[cycle(index, characters.len(), step)].id.clone();
+                }
+                Row::Life(slot) => {
+                    let slot = &mut draft.roster[slot];
+                    slot.life = (slot.life + LIFE_STEP * step)
+                        .clamp(RosterSlotParams::MIN_LIFE, RosterSlotParams::MAX_LIFE);
+                }
+                Row::Power(slot) => {
+                    let slot = &mut draft.roster[slot];
+                    slot.power = step_factor(slot.power, step);
+                }
+                Row::Speed(slot) => {
+                    let slot = &mut draft.roster[slot];
+                    slot.speed = step_factor(slot.speed, step);
+                }
+            }
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            if draft.name.trim().is_empty() {
+                status = "The team needs a name".to_string();
+            } else {
+                // an edited team keeps its id, so that it still replaces the shipped team, and
+                // campaigns and tournaments that refer to it keep working
+                if current < teams.len() {
+                    teams[current] = draft.clone();
+                } else {
+                    draft.id = unique_id(&teams, &draft.name);
+                    teams.push(draft.clone());
+                }
+                status = match save_custom_team(&draft) {
+                    Ok(()) => format!("Saved {}", draft.name),
+                    Err(err) => format!("Could not save the teams: {}", err),
+                };
+                storage::get_mut::<Resources>().teams = teams.clone();
+            }
+        }
+
+        clear_background(LIGHTGRAY);
+        draw_text("Team builder", 40., 50., TEXT_SIZE * 1.5, BLACK);
+        for (i, row) in rows.iter().enumerate() {
+            let value = match *row {
+                Row::Team => {
+                    if current < teams.len() {
+                        format!("Editing: {}", teams[current].name)
+                    } else {
+                        "Editing: new team".to_string()
+                    }
+                }
+                Row::Name => format!("Name: {}", draft.name),
+                Row::PrimaryColor => "Primary color".to_string(),
+                Row::SecondaryColor => "Secondary color".to_string(),
+                Row::Emblem => format!("Emblem: {}", draft.emblem.as_deref().unwrap_or("none")),
+                Row::Character(slot) => {
+                    let name = characters
+                        .iter()
+                        .find(|character| character.id == draft.roster[slot].character)
+                        .map_or("unknown", |character| character.name.as_str());
+                    format!("Slot {}: {}", slot + 1, name)
+                }
+                Row::Life(slot) => format!("    Life: {}", draft.roster[slot].life),
+                Row::Power(slot) => format!("    Power: {:.2}", draft.roster[slot].power),
+                Row::Speed(slot) => format!("    Speed: {:.2}", draft.roster[slot].speed),
+            };
+            let is_selected = i == selected_row;
+            let txt = format!("{}{}", if is_selected { "> " } else { "" }, value);
+            let y = 90. + LINE_HEIGHT * i as f32;
+            draw_text(&txt, 40., y, TEXT_SIZE, if is_selected { RED } else { BLACK });
+            let swatch = match row {
+                Row::PrimaryColor => Some(draft.primary_color),
+                Row::SecondaryColor => Some(draft.secondary_color),
+                _ => None,
+            };
+            if let Some(color) = swatch {
+                draw_rectangle(240., y - TEXT_SIZE * 0.75, 60., TEXT_SIZE * 0.75, color);
+                draw_rectangle_lines(240., y - TEXT_SIZE * 0.75, 60., TEXT_SIZE * 0.75, 2., BLACK);
+            }
+        }
+
+        // preview the kit on the character of the selected slot
+        let slot = match rows[selected_row] {
+            Row::Character(slot) | Row::Life(slot) | Row::Power(slot) | Row::Speed(slot) => slot,
+            _ => 0,
+        };
+        let index = characters
+            .iter()
+            .position(|character| character.id == draft.roster[slot].character)
+            .unwrap_or(0);
+        let preview_x = screen_width() / 2. + 40.;
+        draw_text(&draft.name, preview_x, 110., TEXT_SIZE * 1.25, draft.primary_color);
+        draw_emblem(&draft, &storage::get::<Resources>().textures, vec2(preview_x, 130.), 64.);
+        let preview = &mut previews[index];
+        preview.set_tint(kit_tint(draft.primary_color));
+        preview.update();
+        preview.draw(vec2(preview_x, 220.), 0., false, false);
+
+        let help = "Up/Down: row  Left/Right: change  Type: name  Enter: save  Escape: back";
+        draw_text(help, 40., screen_height() - 30., TEXT_SIZE * 0.75, DARKGRAY);
+        draw_text(&status, 40., screen_height() - 60., TEXT_SIZE * 0.75, BLACK);
+        next_frame().await;
+    }
+}
+
+/// Makes a copy of the team at `index` to edit, or a new team, if `index` is past the last team.
+/// The roster is padded, so that every slot can be edited.
+fn load_draft(teams: &[TeamParams], index: usize, character: &str, roster_size: usize) -> TeamParams {
+    let mut draft = teams.get(index).cloned().unwrap_or_else(|| TeamParams {
+        id: String::new(),
+        name: String::new(),
+        primary_color: PALETTE[0],
+        secondary_color: WHITE,
+        emblem: None,
+        roster: Vec::new(),
+    });
+    while draft.roster.len() < roster_size {
+        draft.roster.push(RosterSlotParams::new(character));
+    }
+    draft
+}
+
+/// Turns the name into an id, adding a number if another team already has it
+fn unique_id(teams: &[TeamParams], name: &str) -> String {
+    let base: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let is_taken = |id: &str| teams.iter().any(|team| team.id == id);
+    let mut id = base.clone();
+    let mut n = 2;
+    while is_taken(&id) {
+        id = format!("{}_{}", base, n);
+        n += 1;
+    }
+    id
+}
+
+fn cycle(index: usize, len: usize, step: i32) -> usize {
+    (index as i32 + step).rem_euclid(len as i32) as usize
+}
+
+fn cycle_color(color: Color, step: i32) -> Color {
+    let index = PALETTE.iter().position(|c| *c == color).unwrap_or(0);
+    PALETTE[cycle(index, PALETTE.len(), step)]
+}
+
+fn step_factor(value: f32, step: i32) -> f32 {
+    let value = value + FACTOR_STEP * step as f32;
+    // rounded, so that stepping back and forth does not pile up float errors in the saved file
+    ((value * 100.).round() / 100.).clamp(RosterSlotParams::MIN_FACTOR, RosterSlotParams::MAX_FACTOR)
+}
@@ -0,0 +1,687 @@
+//! This implements local tournaments, between 4 to 16 teams from `teams.json`. A tournament is
+//! either a single elimination bracket, where the losers of a match drop out, or a round robin,
+//! where every team plays every other team once, and the standings decide the champion.
+//!
+//! Every team is controlled by a human or the AI, which carries over to each of its matches. The
+//! tournament is saved to `tournament.json`, in the data directory, after every match, so it can be
+//! picked up later.
+
+use std::fs;
+use std::path::PathBuf;
+
+use macroquad::prelude::collections::storage;
+use macroquad::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::game::ai::Difficulty;
+use crate::game::match_options::{Controller, MatchOptions};
+use crate::game::match_state::{MatchEnd, MatchResult};
+use crate::game::profile::data_dir;
+use crate::game::resources::Resources;
+use crate::game::scene::menu;
+use crate::game::team::TeamParams;
+use crate::json::{deserialize_json_bytes, serialize_json_bytes};
+use crate::Team;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TournamentFormat {
+    SingleElimination,
+    RoundRobin,
+}
+
+impl TournamentFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TournamentFormat::SingleElimination => "Single elimination",
+            TournamentFormat::RoundRobin => "Round robin",
+        }
+    }
+}
+
+/// A team taking part in a tournament
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TournamentEntry {
+    /// The id of the team, from `teams.json`
+    pub team: String,
+    pub controller: Controller,
+}
+
+/// A match between two entries. In single elimination, a team without an opponent gets a bye,
+/// and goes through to the next round without playing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+    /// The round of the tournament this is played in, starting at 0
+    pub round: usize,
+    /// The index in `Tournament::entries` of the team that plays as team one, or `None` for a bye
+    pub home: Option<usize>,
+    /// The index in `Tournament::entries` of the team that plays as team two, or `None` for a bye
+    pub away: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winner: Option<usize>,
+    /// The rounds won by the home team
+    #[serde(default)]
+    pub home_rounds: u32,
+    /// The rounds won by the away team
+    #[serde(default)]
+    pub away_rounds: u32,
+}
+
+impl Fixture {
+    fn new(round: usize, home: Option<usize>, away: Option<usize>) -> Self {
+        // a team with a bye wins right away
+        let winner = match (home, away) {
+            (Some(home), None) => Some(home),
+            (None, Some(away)) => Some(away),
+            _ => None,
+        };
+        Fixture {
+            round,
+            home,
+            away,
+            winner,
+            home_rounds: 0,
+            away_rounds: 0,
+        }
+    }
+
+    pub fn is_played(&self) -> bool {
+        self.winner.is_some()
+    }
+}
+
+/// The record of an entry in a round robin
+#[derive(Debug, Clone, Default)]
+pub struct Standing {
+    /// The index in `Tournament::entries`
+    pub entry: usize,
+    pub played: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub rounds_won: u32,
+    pub rounds_lost: u32,
+}
+
+impl Standing {
+    pub fn round_difference(&self) -> i32 {
+        self.rounds_won as i32 - self.rounds_lost as i32
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tournament {
+    pub format: TournamentFormat,
+    pub entries: Vec<TournamentEntry>,
+    /// Every fixture so far, in the order they are played. In single elimination, the fixtures of
+    /// a round are added once every fixture of the round before it has been played
+    pub fixtures: Vec<Fixture>,
+}
+
+impl Tournament {
+    pub const TOURNAMENT_FILE: &'static str = "tournament";
+
+    pub const MIN_TEAMS: usize = 4;
+    pub const MAX_TEAMS: usize = 16;
+
+    pub fn new(format: TournamentFormat, entries: Vec<TournamentEntry>) -> Self {
+        let fixtures = match format {
+            TournamentFormat::SingleElimination => Self::first_bracket_round(entries.len()),
+            TournamentFormat::RoundRobin => Self::round_robin_fixtures(entries.len()),
+        };
+        Tournament {
+            format,
+            entries,
+            fixtures,
+        }
+    }
+
+    /// Fills the bracket up to a power of two with byes, which go to the top seeds. Seeds are
+    /// placed so that the top seeds can only meet in the latest possible round
+    fn first_bracket_round(entry_count: usize) -> Vec<Fixture> {
+        let size = entry_count.next_power_of_two();
+        let mut order = vec![0];
+        while order.len() < size {
+            let len = order.len() * 2;
+            order = order.iter().flat_map(|&seed| [seed, len - 1 - seed]).collect();
+        }
+        order
+            .chunks(2)
+            .map(|pair| {
+                let seed = |seed: usize| Some(seed).filter(|&seed| seed < entry_count);
+                Fixture::new(0, seed(pair[0]), seed(pair[1]))
+            })
+            .collect()
+    }
+
+    /// Uses the circle method, where one entry stays put, and the others rotate around it, so
+    /// that every entry plays once per round. With an odd number of entries, one of them sits
+    /// every round out
+    fn round_robin_fixtures(entry_count: usize) -> Vec<Fixture> {
+        let mut circle: Vec<Option<usize>> = (0..entry_count).map(Some).collect();
+        if circle.len() % 2 == 1 {
+            circle.push(None);
+        }
+        let size = circle.len();
+        let mut fixtures = Vec::new();
+        for round in 0..size - 1 {
+            for i in 0..size / 2 {
+                let (home, away) = (circle[i], circle[size - 1 - i]);
+                if home.is_some() && away.is_some() {
+                    // alternate sides, so no team is always team one
+                    let (home, away) = if round % 2 == 0 { (home, away) } else { (away, home) };
+                    fixtures.push(Fixture::new(round, home, away));
+                }
+            }
+            circle[1..].rotate_right(1);
+        }
+        fixtures
+    }
+
+    /// The index of the next fixture to be played, if there is one
+    pub fn next_fixture(&self) -> Option<usize> {
+        self.fixtures.iter().position(|fixture| !fixture.is_played())
+    }
+
+    /// Records the result of a fixture, and in single elimination, draws up the next round once
+    /// the current one is over
+    pub fn record_result(&mut self, fixture_index: usize, result: &MatchResult) {
+        let fixture = &mut self.fixtures[fixture_index];
+        fixture.winner = match result.winner {
+            Team::One => fixture.home,
+            Team::Two => fixture.away,
+        };
+        fixture.home_rounds = result.round_wins.get(&Team::One).copied().unwrap_or(0);
+        fixture.away_rounds = result.round_wins.get(&Team::Two).copied().unwrap_or(0);
+        let round = fixture.round;
+        if self.format == TournamentFormat::SingleElimination {
+            let winners: Vec<Option<usize>> = self.fixtures.iter()
+                .filter(|fixture| fixture.round == round)
+                .map(|fixture| fixture.winner)
+                .collect();
+            if winners.len() > 1 && winners.iter().all(Option::is_some) {
+                for pair in winners.chunks(2) {
+                    self.fixtures.push(Fixture::new(round + 1, pair[0], pair[1]));
+                }
+            }
+        }
+    }
+
+    /// The number of rounds the tournament will take, once it is over
+    pub fn round_count(&self) -> usize {
+        match self.format {
+            TournamentFormat::SingleElimination => self.entries.len().next_power_of_two().trailing_zeros() as usize,
+            TournamentFormat::RoundRobin => self.fixtures.iter().map(|fixture| fixture.round + 1).max().unwrap_or(0),
+        }
+    }
+
+    /// The index of the entry that won the tournament, once every fixture has been played
+    pub fn champion(&self) -> Option<usize> {
+        if self.next_fixture().is_some() {
+            return None;
+        }
+        match self.format {
+            TournamentFormat::SingleElimination => self.fixtures.last().and_then(|fixture| fixture.winner),
+            TournamentFormat::RoundRobin => self.standings().first().map(|standing| standing.entry),
+        }
+    }
+
+    /// The record of every entry, best first. Entries are ranked by wins, then by round
+    /// difference, then by rounds won
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = (0..self.entries.len())
+            .map(|entry| Standing { entry, ..Default::default() })
+            .collect();
+        for fixture in self.fixtures.iter().filter(|fixture| fixture.is_played()) {
+            // byes do not count as played
+            let (home, away) = match (fixture.home, fixture.away) {
+                (Some(home), Some(away)) => (home, away),
+                _ => continue,
+            };
+            let sides = [(home, fixture.home_rounds, fixture.away_rounds), (away, fixture.away_rounds, fixture.home_rounds)];
+            for (entry, won, lost) in sides {
+                let standing = &mut standings[entry];
+                standing.played += 1;
+                if fixture.winner == Some(entry) {
+                    standing.wins += 1;
+                } else {
+                    standing.losses += 1;
+                }
+                standing.rounds_won += won;
+                standing.rounds_lost += lost;
+            }
+        }
+        standings.sort_by(|a, b| {
+            b.wins.cmp(&a.wins)
+                .then(b.round_difference().cmp(&a.round_difference()))
+                .then(b.rounds_won.cmp(&a.rounds_won))
+                .then(a.entry.cmp(&b.entry))
+        });
+        standings
+    }
+}
+
+/// The tournament is saved in the data directory, like profiles, as the resources directory may
+/// not be writable
+fn tournament_path() -> PathBuf {
+    data_dir()
+        .join(Tournament::TOURNAMENT_FILE)
+        .with_extension(Resources::RESOURCE_FILES_EXTENSION)
+}
+
+/// Writes `tournament` to the `tournament.json` file in the data directory
+pub fn save_tournament(tournament: &Tournament) -> Result<()> {
+    fs::create_dir_all(data_dir())?;
+    let bytes = serialize_json_bytes(tournament)?;
+    fs::write(tournament_path(), bytes)?;
+    Ok(())
+}
+
+/// Loads the saved tournament, if there is one
+pub fn load_tournament() -> Option<Tournament> {
+    let bytes = fs::read(tournament_path()).ok()?;
+    deserialize_json_bytes(&bytes).ok()
+}
+
+/// Removes the saved tournament, once it is over
+pub fn remove_tournament() {
+    let _ = fs::remove_file(tournament_path());
+}
+
+const TEXT_SIZE: f32 = 24.;
+const SMALL_TEXT_SIZE: f32 = 18.;
+const LINE_HEIGHT: f32 = 28.;
+
+/// The controllers a tournament entry can cycle through
+const CONTROLLERS: [Controller; 4] = [
+    Controller::Human,
+    Controller::Cpu(Difficulty::Easy),
+    Controller::Cpu(Difficulty::Normal),
+    Controller::Cpu(Difficulty::Hard),
+];
+
+/// Runs the tournament mode, until it is left with Escape. `options` are used for every match,
+/// with the teams and controllers of each fixture swapped in. Returns whether a match was quit to
+/// the main menu
+pub async fn tournament(options: &MatchOptions) -> bool {
+    let teams = storage::get::<Resources>().teams.clone();
+    let saved = load_tournament();
+    let resume = match saved {
+        Some(saved) => match menu("Tournament", &["Continue tournament", "New tournament"]).await {
+            Some(0) => Some(saved),
+            Some(_) => None,
+            None => return false,
+        },
+        None => None,
+    };
+    next_frame().await;
+    let mut tournament = match resume {
+        Some(tournament) => tournament,
+        None => match tournament_setup(&teams).await {
+            Some(tournament) => tournament,
+            None => return false,
+        },
+    };
+    let mut status = String::new();
+    if let Err(err) = save_tournament(&tournament) {
+        status = format!("Could not save the tournament: {}", err);
+    }
+    next_frame().await;
+    loop {
+        if is_key_pressed(KeyCode::Escape) {
+            return false;
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            match tournament.next_fixture() {
+                Some(fixture_index) => {
+                    let mut match_options = options.clone();
+                    let fixture = &tournament.fixtures[fixture_index];
+                    for (team, entry) in [(Team::One, fixture.home), (Team::Two, fixture.away)] {
+                        let entry = &tournament.entries[entry.unwrap()];
+                        match team {
+                            Team::One => match_options.team_one = entry.controller,
+                            Team::Two => match_options.team_two = entry.controller,
+                        }
+                        match_options.set_team(team, teams.iter().find(|params| params.id == entry.team));
+                    }
+                    let result = match crate::local_game(&mut match_options).await {
+                        MatchEnd::Finished(result) => result,
+                        MatchEnd::QuitToMenu => return true,
+                    };
+                    tournament.record_result(fixture_index, &result);
+                    status = match save_tournament(&tournament) {
+                        Ok(()) => String::new(),
+                        Err(err) => format!("Could not save the tournament: {}", err),
+                    };
+                    next_frame().await;
+                    continue;
+                }
+                None => {
+                    remove_tournament();
+                    return false;
+                }
+            }
+        }
+
+        clear_background(LIGHTGRAY);
+        let title = format!("Tournament: {}", tournament.format.label());
+        draw_text(&title, 40., 50., TEXT_SIZE * 1.5, BLACK);
+        match tournament.format {
+            TournamentFormat::SingleElimination => draw_bracket(&tournament, &teams),
+            TournamentFormat::RoundRobin => draw_round_robin(&tournament, &teams),
+        }
+        let footer = match (tournament.champion(), tournament.next_fixture()) {
+            (Some(champion), _) => format!("Champion: {}!  Enter: finish", entry_name(&tournament, &teams, champion)),
+            (None, Some(fixture_index)) => {
+                let fixture = &tournament.fixtures[fixture_index];
+                format!(
+                    "Next: {} vs {}  Enter: play  Escape: save and leave",
+                    fixture_name(&tournament, &teams, fixture.home),
+                    fixture_name(&tournament, &teams, fixture.away),
+                )
+            }
+            (None, None) => String::new(),
+        };
+        draw_text(&footer, 40., screen_height() - 30., TEXT_SIZE * 0.75, DARKGRAY);
+        draw_text(&status, 40., screen_height() - 60., TEXT_SIZE * 0.75, RED);
+        next_frame().await;
+    }
+}
+
+/// Picks the format, the number of teams and who controls each team. Returns `None` if the setup
+/// was left with Escape
+async fn tournament_setup(teams: &[TeamParams]) -> Option<Tournament> {
+    let max_teams = teams.len().min(Tournament::MAX_TEAMS);
+    let mut format = TournamentFormat::SingleElimination;
+    let mut team_count = max_teams.clamp(Tournament::MIN_TEAMS, 8);
+    // the order of the teams is their seeding
+    let mut seeding: Vec<usize> = (0..teams.len()).collect();
+    let mut controllers = vec![Controller::Cpu(Difficulty::Normal); teams.len()];
+    if let Some(first) = controllers.first_mut() {
+        *first = Controller::Human;
+    }
+    let mut selected_row = 0;
+    loop {
+        if is_key_pressed(KeyCode::Escape) {
+            return None;
+        }
+        if max_teams < Tournament::MIN_TEAMS {
+            clear_background(LIGHTGRAY);
+            draw_text("Tournament", 40., 50., TEXT_SIZE * 1.5, BLACK);
+            let txt = format!("A tournament needs at least {} teams in teams.json", Tournament::MIN_TEAMS);
+            draw_text(&txt, 40., 110., TEXT_SIZE, BLACK);
+            draw_text("Escape: back", 40., screen_height() - 30., TEXT_SIZE * 0.75, DARKGRAY);
+            next_frame().await;
+            continue;
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            let entries = seeding[..team_count]
+                .iter()
+                .map(|&i| TournamentEntry {
+                    team: teams[i].id.clone(),
+                    controller: controllers[i],
+                })
+                .collect();
+            return Some(Tournament::new(format, entries));
+        }
+        // the format and team count rows come before a row for every team
+        let rows = team_count + 2;
+        selected_row = selected_row.min(rows - 1);
+        if is_key_pressed(KeyCode::Up) {
+            selected_row = (selected_row + rows - 1) % rows;
+        }
+        if is_key_pressed(KeyCode::Down) {
+            selected_row = (selected_row + 1) % rows;
+        }
+        if is_key_pressed(KeyCode::R) {
+            for i in (1..seeding.len()).rev() {
+                seeding.swap(i, rand::gen_range(0, i + 1));
+            }
+        }
+        let step = if is_key_pressed(KeyCode::Left) {
+            -1
+        } else if is_key_pressed(KeyCode::Right) {
+            1
+        } else {
+            0
+        };
+        if step != 0 {
+            match selected_row {
+                0 => {
+                    format = match format {
+                        TournamentFormat::SingleElimination => TournamentFormat::RoundRobin,
+                        TournamentFormat::RoundRobin => TournamentFormat::SingleElimination,
+                    }
+                }
+                1 => {
+                    team_count = (team_count as i32 + step)
+                        .clamp(Tournament::MIN_TEAMS as i32, max_teams as i32) as usize;
+                }
+                row => {
+                    let team = seeding[row - 2];
+                    let index = CONTROLLERS.iter().position(|&c| c == controllers[team]).unwrap_or(0);
+                    let len = CONTROLLERS.len() as i32;
+                    controllers[team] = CONTROLLERS[(index as i32 + step).rem_euclid(len) as usize];
+                }
+            }
+        }
+
+        clear_background(LIGHTGRAY);
+        draw_text("New tournament", 40., 50., TEXT_SIZE * 1.5, BLACK);
+        let mut lines = vec![format!("Format: {}", format.label()), format!("Teams: {}", team_count)];
+        for (seed, &team) in seeding[..team_count].iter().enumerate() {
+            lines.push(format!("{}. {}: {}", seed + 1, teams[team].name, controllers[team].label()));
+        }
+        for (i, line) in lines.iter().enumerate() {
+            let is_selected = i == selected_row;
+            let txt = format!("{}{}", if is_selected { "> " } else { "" }, line);
+            let y = 90. + LINE_HEIGHT * i as f32;
+            draw_text(&txt, 40., y, TEXT_SIZE, if is_selected { RED } else { BLACK });
+        }
+        let help = "Up/Down: row  Left/Right: change  R: shuffle seeding  Enter: start  Escape: back";
+        draw_text(help, 40., screen_height() - 30., TEXT_SIZE * 0.75, DARKGRAY);
+        next_frame().await;
+    }
+}
+
+/// Draws every round of the bracket as a column, with the winner of each fixture in red
+fn draw_bracket(tournament: &Tournament, teams: &[TeamParams]) {
+    let rounds = tournament.round_count().max(1);
+    let column_width = (screen_width() - 80.) / rounds as f32;
+    for round in 0..rounds {
+        let x = 40. + column_width * round as f32;
+        let label = if round + 1 == rounds { "Final".to_string() } else { format!("Round {}", round + 1) };
+        draw_text(&label, x, 90., TEXT_SIZE, BLACK);
+        let fixtures = tournament.fixtures.iter().filter(|fixture| fixture.round == round);
+        for (i, fixture) in fixtures.enumerate() {
+            let y = 120. + (SMALL_TEXT_SIZE * 2. + 12.) * i as f32 * (1 << round) as f32;
+            for (line, (entry, rounds_won)) in [(fixture.home, fixture.home_rounds), (fixture.away, fixture.away_rounds)].into_iter().enumerate() {
+                let color = if entry.is_some() && fixture.winner == entry { RED } else { BLACK };
+                let score = if fixture.is_played() && fixture.home.is_some() && fixture.away.is_some() {
+                    format!(" ({})", rounds_won)
+                } else {
+                    String::new()
+                };
+                let txt = format!("{}{}", fixture_name(tournament, teams, entry), score);
+                draw_text(&txt, x, y + SMALL_TEXT_SIZE * line as f32, SMALL_TEXT_SIZE, color);
+            }
+        }
+    }
+}
+
+/// Draws the standings table, with the results of the fixtures played so far next to it
+fn draw_round_robin(tournament: &Tournament, teams: &[TeamParams]) {
+    draw_text("Team", 40., 90., TEXT_SIZE, BLACK);
+    draw_text("P   W   L   RD", 300., 90., TEXT_SIZE, BLACK);
+    for (i, standing) in tournament.standings().iter().enumerate() {
+        let y = 90. + LINE_HEIGHT * (i + 1) as f32;
+        let name = format!("{}. {}", i + 1, entry_name(tournament, teams, standing.entry));
+        draw_text(&name, 40., y, TEXT_SIZE, BLACK);
+        let record = format!(
+            "{:<3} {:<3} {:<3} {:+}",
+            standing.played, standing.wins, standing.losses, standing.round_difference()
+        );
+        draw_text(&record, 300., y, TEXT_SIZE, BLACK);
+    }
+    let x = screen_width() / 2. + 40.;
+    draw_text("Results", x, 90., TEXT_SIZE, BLACK);
+    let played: Vec<&Fixture> = tournament.fixtures.iter().filter(|fixture| fixture.is_played()).collect();
+    // only the latest results fit on screen
+    let shown = ((screen_height() - 220.) / SMALL_TEXT_SIZE).max(0.) as usize;
+    for (i, fixture) in played.iter().rev().take(shown).enumerate() {
+        let txt = format!(
+            "{} {} - {} {}",
+            fixture_name(tournament, teams, fixture.home),
+            fixture.home_rounds,
+            fixture.away_rounds,
+            fixture_name(tournament, teams, fixture.away),
+        );
+        draw_text(&txt, x, 120. + SMALL_TEXT_SIZE * i as f32, SMALL_TEXT_SIZE, BLACK);
+    }
+}
+
+fn entry_name(tournament: &Tournament, teams: &[TeamParams], entry: usize) -> String {
+    let id = &tournament.entries[entry].team;
+    teams.iter()
+        .find(|params| &params.id == id)
+        .map_or_else(|| id.clone(), |params| params.name.clone())
+}
+
+/// The name of a side of a fixture, which is either a team or a bye
+fn fixture_name(tournament: &Tournament, teams: &[TeamParams], entry: Option<usize>) -> String {
+    match entry {
+        Some(entry) => entry_name(tournament, teams, entry),
+        None => "(bye)".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn entries(count: usize) -> Vec<TournamentEntry> {
+        (0..count)
+            .map(|i| TournamentEntry { team: format!("team_{}", i), controller: Controller::Cpu(Difficulty::Normal) })
+            .collect()
+    }
+
+    fn result(home_rounds: u32, away_rounds: u32) -> MatchResult {
+        MatchResult {
+            winner: if home_rounds > away_rounds { Team::One } else { Team::Two },
+            round_wins: HashMap::from([(Team::One, home_rounds), (Team::Two, away_rounds)]),
+            time_played: 0.,
+            stats: Vec::new(),
+        }
+    }
+
+    fn sides(fixture: &Fixture) -> (Option<usize>, Option<usize>) {
+        (fixture.home, fixture.away)
+    }
+
+    #[test]
+    fn test_first_bracket_round_seeding() {
+        let fixtures = Tournament::first_bracket_round(8);
+        let pairs: Vec<_> = fixtures.iter().map(sides).collect();
+        assert_eq!(pairs, [
+            (Some(0), Some(7)),
+            (Some(3), Some(4)),
+            (Some(1), Some(6)),
+            (Some(2), Some(5)),
+        ]);
+        assert!(fixtures.iter().all(|fixture| fixture.round == 0 && !fixture.is_played()));
+    }
+
+    #[test]
+    fn test_first_bracket_round_byes() {
+        // the two missing seeds are byes for the top two seeds, who go through right away
+        let fixtures = Tournament::first_bracket_round(6);
+        let pairs: Vec<_> = fixtures.iter().map(sides).collect();
+        assert_eq!(pairs, [
+            (Some(0), None),
+            (Some(3), Some(4)),
+            (Some(1), None),
+            (Some(2), Some(5)),
+        ]);
+        let winners: Vec<_> = fixtures.iter().map(|fixture| fixture.winner).collect();
+        assert_eq!(winners, [Some(0), None, Some(1), None]);
+    }
+
+    #[test]
+    fn test_round_robin_fixtures() {
+        for entry_count in [4, 5] {
+            let fixtures = Tournament::round_robin_fixtures(entry_count);
+            assert_eq!(fixtures.len(), entry_count * (entry_count - 1) / 2);
+            // every pair meets exactly once
+            for a in 0..entry_count {
+                for b in a + 1..entry_count {
+                    let meetings = fixtures.iter()
+                        .filter(|fixture| sides(fixture) == (Some(a), Some(b)) || sides(fixture) == (Some(b), Some(a)))
+                        .count();
+                    assert_eq!(meetings, 1, "{} and {} of {}", a, b, entry_count);
+                }
+            }
+            // nobody plays twice in a round
+            let rounds = fixtures.iter().map(|fixture| fixture.round).max().unwrap() + 1;
+            for round in 0..rounds {
+                let mut playing: Vec<usize> = fixtures.iter()
+                    .filter(|fixture| fixture.round == round)
+                    .flat_map(|fixture| [fixture.home.unwrap(), fixture.away.unwrap()])
+                    .collect();
+                let count = playing.len();
+                playing.sort();
+                playing.dedup();
+                assert_eq!(playing.len(), count);
+            }
+        }
+    }
+
+    #[test]
+    fn test_record_result_advances_the_bracket() {
+        let mut tournament = Tournament::new(TournamentFormat::SingleElimination, entries(4));
+        assert_eq!(tournament.fixtures.len(), 2);
+        tournament.record_result(0, &result(2, 1));
+        // the next round is only drawn up once the whole round has been played
+        assert_eq!(tournament.fixtures.len(), 2);
+        assert_eq!(tournament.next_fixture(), Some(1));
+        tournament.record_result(1, &result(0, 2));
+        assert_eq!(tournament.fixtures.len(), 3);
+        let last = &tournament.fixtures[2];
+        assert_eq!((last.round, last.home, last.away), (1, Some(0), Some(2)));
+        assert_eq!(tournament.champion(), None);
+        tournament.record_result(2, &result(1, 2));
+        assert_eq!(tournament.fixtures.len(), 3);
+        assert_eq!(tournament.champion(), Some(2));
+        assert_eq!((tournament.fixtures[2].home_rounds, tournament.fixtures[2].away_rounds), (1, 2));
+    }
+
+    #[test]
+    fn test_standings() {
+        let mut tournament = Tournament::new(TournamentFormat::RoundRobin, entries(4));
+        // 0, 1 and 2 beat each other in a circle, and 3 loses every match, so the round difference
+        // decides, which 1 wins by winning 3-0
+        let winners_and_rounds = |fixture: &Fixture| {
+            let (home, away) = (fixture.home.unwrap(), fixture.away.unwrap());
+            let wins = |entry: usize, other: usize| matches!((entry, other), (0, 1) | (2, 0) | (1, 2) | (_, 3));
+            let margin = |entry: usize| if entry == 1 { (3, 0) } else { (2, 1) };
+            if wins(home, away) {
+                margin(home)
+            } else {
+                let (won, lost) = margin(away);
+                (lost, won)
+            }
+        };
+        while let Some(index) = tournament.next_fixture() {
+            let (home_rounds, away_rounds) = winners_and_rounds(&tournament.fixtures[index]);
+            tournament.record_result(index, &result(home_rounds, away_rounds));
+        }
+        let standings = tournament.standings();
+        let order: Vec<_> = standings.iter().map(|standing| standing.entry).collect();
+        assert_eq!(order, [1, 0, 2, 3]);
+        assert_eq!((standings[0].wins, standings[0].losses, standings[0].played), (2, 1, 3));
+        assert_eq!((standings[3].wins, standings[3].losses), (0, 3));
+        assert_eq!(tournament.champion(), Some(1));
+    }
+}
@@ -0,0 +1,269 @@
+//! This implements the `--validate-assets` command, which checks the declarations of textures,
+//! characters and balls for mistakes that would otherwise only show up once the game is running,
+//! as a panic or as a broken sprite. The files are read directly, instead of through `Resources`,
+//! so that the command runs without opening a window, on a build server for example.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use macroquad::prelude::*;
+
+use serde::de::DeserializeOwned;
+
+use crate::game::animations::AnimationMetadata;
+use crate::game::ball::BallParams;
+use crate::game::character::{PlayerAnimations, PlayerCharacterParams};
+use crate::game::resources::{Resources, TextureMetadata};
+
+/// A mistake in one of the resource files
+#[derive(Debug, Clone)]
+pub struct AssetProblem {
+    /// The file the mistake is in
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for AssetProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+#[derive(Default)]
+struct Validator {
+    problems: Vec<AssetProblem>,
+}
+
+impl Validator {
+    fn report(&mut self, path: &Path, message: String) {
+        self.problems.push(AssetProblem { path: path.to_path_buf(), message });
+    }
+
+    /// Reads a resource file, reporting it if it can not be read or parsed
+    fn read_file<T: DeserializeOwned + Default>(&mut self, path: &Path) -> T {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                self.report(path, format!("the file can not be read: {}", err));
+                return T::default();
+            }
+        };
+        match serde_json::from_slice(&bytes) {
+            Ok(value) => value,
+            Err(err) => {
+                self.report(path, format!("the file can not be parsed: {}", err));
+                T::default()
+            }
+        }
+    }
+
+    fn check_unique_ids<'a>(&mut self, path: &Path, kind: &str, ids: impl Iterator<Item = &'a str>) {
+        let mut seen = Vec::new();
+        for id in ids {
+            if seen.contains(&id) {
+                self.report(path, format!("there is more than one {} with the id '{}'", kind, id));
+            } else {
+                seen.push(id);
+            }
+        }
+    }
+
+    /// Checks that the texture of an animated sprite exists, and that every frame of its
+    /// animations is inside the spritesheet
+    fn check_animations(
+        &mut self,
+        path: &Path,
+        owner: &str,
+        texture_id: &str,
+        frame_size: Option<UVec2>,
+        animations: &[AnimationMetadata],
+        textures: &HashMap<&str, (&TextureMetadata, Option<UVec2>)>,
+    ) {
+        self.check_unique_ids(path, &format!("animation of {}", owner), animations.iter().map(|animation| animation.id.as_str()));
+        let (meta, texture_size) = match textures.get(texture_id) {
+            Some(&texture) => texture,
+            None => {
+                self.report(path, format!("{} uses the texture '{}', which does not exist", owner, texture_id));
+                return;
+            }
+        };
+        // the size of a texture that can not be loaded is unknown, which is reported by itself
+        let texture_size = match texture_size {
+            Some(texture_size) => texture_size,
+            None => return,
+        };
+        let frame_size = frame_size.or(meta.sprite_size).unwrap_or(texture_size);
+        for animation in animations {
+            if animation.frames == 0 {
+                self.report(path, format!("the animation '{}' of {} has no frames", animation.id, owner));
+                continue;
+            }
+            let size = uvec2(animation.frames * frame_size.x, (animation.row + 1) * frame_size.y);
+            if size.x > texture_size.x || size.y > texture_size.y {
+                self.report(path, format!(
+                    "the animation '{}' of {} needs {}x{} pixels of the texture '{}', which is only {}x{}",
+                    animation.id, owner, size.x, size.y, texture_id, texture_size.x, texture_size.y,
+                ));
+            }
+        }
+    }
+}
+
+/// Checks `textures.json`, `player_characters.json` and `balls.json`, in the given assets directory,
+/// and returns every problem that was found
+pub fn validate_assets(assets_dir: &str) -> Vec<AssetProblem> {
+    let mut validator = Validator::default();
+    let textures_path = Resources::file_path(assets_dir, Resources::TEXTURES_FILE);
+    let characters_path = Resources::file_path(assets_dir, Resources::PLAYER_CHARACTERS_FILE);
+    let balls_path = Resources::file_path(assets_dir, Resources::BALLS_FILE);
+    let textures: Vec<TextureMetadata> = validator.read_file(&textures_path);
+    let characters: Vec<PlayerCharacterParams> = validator.read_file(&characters_path);
+    let balls: Vec<BallParams> = validator.read_file(&balls_path);
+
+    validator.check_unique_ids(&textures_path, "texture", textures.iter().map(|texture| texture.id.as_str()));
+    validator.check_unique_ids(&characters_path, "character", characters.iter().map(|character| character.id.as_str()));
+    validator.check_unique_ids(&balls_path, "ball", balls.iter().map(|ball| ball.id.as_str()));
+
+    // the textures by id, with their size, if their file can be read
+    let mut texture_sizes = HashMap::new();
+    for texture in &textures {
+        let file_path = Path::new(assets_dir).join(&texture.path);
+        let size = if !file_path.exists() {
+            validator.report(&textures_path, format!("the file of the texture '{}' does not exist: {}", texture.id, file_path.display()));
+            None
+        } else {
+            match image::image_dimensions(&file_path) {
+                Ok((width, height)) => Some(uvec2(width, height)),
+                Err(err) => {
+                    validator.report(&textures_path, format!("the file of the texture '{}' can not be read: {}", texture.id, err));
+                    None
+                }
+            }
+        };
+        texture_sizes.insert(texture.id.as_str(), (texture, size));
+    }
+
+    for character in &characters {
+        let owner = format!("the character '{}'", character.id);
+        let animations: Vec<AnimationMetadata> = character.animation.animations.clone().into();
+        for id in PlayerAnimations::REQUIRED_IDS {
+            if !animations.iter().any(|animation| animation.id == id) {
+                validator.report(&characters_path, format!("{} has no '{}' animation", owner, id));
+            }
+        }
+        let params = &character.animation;
+        validator.check_animations(&characters_path, &owner, &params.texture_id, params.frame_size, &animations, &texture_sizes);
+    }
+
+    for ball in &balls {
+        let owner = format!("the ball '{}'", ball.id);
+        let animations: Vec<AnimationMetadata> = ball.animation.animations.clone().into();
+        let params = &ball.animation;
+        validator.check_animations(&balls_path, &owner, &params.texture_id, params.frame_size, &animations, &texture_sizes);
+    }
+
+    validator.problems
+}
+
+/// Runs the `--validate-assets` command: prints every problem with the assets, and returns the
+/// exit code, which is 1 if there were any
+pub fn validate_assets_command(assets_dir: &str) -> i32 {
+    let problems = validate_assets(assets_dir);
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    if problems.is_empty() {
+        println!("No problems found in the assets of '{}'", assets_dir);
+        0
+    } else {
+        println!("Found {} problem(s) in the assets of '{}'", problems.len(), assets_dir);
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a set of resource files to a new directory in the temp dir, and returns its path.
+    /// The player texture fits every default animation, with 8x8 frames, and so does the ball
+    /// texture
+    fn write_fixture(name: &str, textures: &str, characters: &str, balls: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("super_dodge_ball_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        image::RgbaImage::new(64, 88).save(dir.join("player.png")).unwrap();
+        image::RgbaImage::new(40, 16).save(dir.join("ball.png")).unwrap();
+        let assets_dir = dir.to_str().unwrap().to_string();
+        for (file, contents) in [
+            (Resources::TEXTURES_FILE, textures),
+            (Resources::PLAYER_CHARACTERS_FILE, characters),
+            (Resources::BALLS_FILE, balls),
+        ] {
+            fs::write(Resources::file_path(&assets_dir, file), contents).unwrap();
+        }
+        assets_dir
+    }
+
+    const TEXTURES: &str = r#"[
+        {"id": "player", "path": "player.png", "type": "spritesheet", "sprite_size": {"x": 8, "y": 8}},
+        {"id": "ball", "path": "ball.png", "type": "spritesheet", "sprite_size": {"x": 8, "y": 8}}
+    ]"#;
+    const CHARACTERS: &str = r#"[{"id": "bandit", "name": "Bandit", "texture": "player"}]"#;
+    const BALLS: &str = r#"[{"id": "soccer-ball", "name": "Soccer-Ball", "texture": "ball"}]"#;
+
+    fn messages(problems: &[AssetProblem]) -> Vec<&str> {
+        problems.iter().map(|problem| problem.message.as_str()).collect()
+    }
+
+    #[test]
+    fn test_valid_assets() {
+        let assets_dir = write_fixture("valid_assets", TEXTURES, CHARACTERS, BALLS);
+        assert!(messages(&validate_assets(&assets_dir)).is_empty());
+        assert_eq!(validate_assets_command(&assets_dir), 0);
+    }
+
+    #[test]
+    fn test_invalid_assets() {
+        let textures = r#"[
+            {"id": "player", "path": "player.png", "type": "spritesheet", "sprite_size": {"x": 8, "y": 8}},
+            {"id": "ball", "path": "ball.png", "type": "spritesheet", "sprite_size": {"x": 8, "y": 8}},
+            {"id": "ball", "path": "ball.png", "type": "spritesheet", "sprite_size": {"x": 8, "y": 8}},
+            {"id": "ghost", "path": "ghost.png"}
+        ]"#;
+        let characters = r#"[
+            {"id": "bandit", "name": "Bandit", "texture": "player", "animations": [
+                {"id": "idle", "row": 0, "frames": 9, "fps": 8},
+                {"id": "walk", "row": 1, "frames": 8, "fps": 8}
+            ]},
+            {"id": "phantom", "name": "Phantom", "texture": "invisible"}
+        ]"#;
+        let assets_dir = write_fixture("invalid_assets", textures, characters, BALLS);
+        let problems = validate_assets(&assets_dir);
+        let ghost_path = Path::new(&assets_dir).join("ghost.png");
+        assert_eq!(messages(&problems), [
+            "there is more than one texture with the id 'ball'".to_string(),
+            format!("the file of the texture 'ghost' does not exist: {}", ghost_path.display()),
+            "the character 'bandit' has no 'move' animation".to_string(),
+            "the animation 'idle' of the character 'bandit' needs 72x8 pixels of the texture 'player', which is only 64x88".to_string(),
+            "the character 'phantom' uses the texture 'invisible', which does not exist".to_string(),
+        ]);
+        let textures_path = Resources::file_path(&assets_dir, Resources::TEXTURES_FILE);
+        let characters_path = Resources::file_path(&assets_dir, Resources::PLAYER_CHARACTERS_FILE);
+        let paths: Vec<&Path> = problems.iter().map(|problem| problem.path.as_path()).collect();
+        assert_eq!(paths, [&textures_path, &textures_path, &characters_path, &characters_path, &characters_path]);
+        assert_eq!(validate_assets_command(&assets_dir), 1);
+    }
+
+    #[test]
+    fn test_unreadable_assets() {
+        let assets_dir = write_fixture("unreadable_assets", "[{", CHARACTERS, BALLS);
+        let problems = validate_assets(&assets_dir);
+        assert!(problems[0].message.starts_with("the file can not be parsed"), "{:?}", problems);
+        // without textures, every texture that is used is missing
+        assert_eq!(problems.len(), 3);
+        assert_eq!(validate_assets_command(&assets_dir), 1);
+    }
+}
@@ -12,10 +12,23 @@ use game::player::Player;
 use crate::game::animations::{AnimationParams, AnimationPlayer};
 use crate::game::ball::animations::BallAnimations;
 use crate::game::ball::{Ball, BallState};
-use crate::game::character::PlayerCharacterParams;
+use crate::game::ai::AiController;
+use crate::game::audio::{load_audio, Audio};
+use crate::game::match_options::{Controller, MatchOptions};
+use crate::game::match_state::{MatchEnd, MatchState};
+use crate::game::pause_menu::{PauseChoice, PauseMenu};
+use crate::game::particles::Particles;
 use crate::game::player::PlayerState;
 use crate::game::resources::{load_resources, Resources};
+use crate::game::settings::{load_settings, settings_menu};
+use crate::game::profile::{load_profile, update_profile, Profile};
+use crate::game::team::{kit_tint, RosterSlotParams};
+use crate::game::hot_reload::ResourceWatcher;
+use crate::game::hud::draw_hud;
+use crate::game::validate_assets::validate_assets_command;
+use crate::game::scene::SceneManager;
 use crate::json::is_false;
+use crate::math::circle_rect_contact;
 
 pub mod helpers;
 pub mod error;
@@ -26,7 +39,21 @@ pub mod noise;
 
 const PLAYER_HEIGHT: f32 = 54.;
 const PLAYER_WIDTH: f32 = 54.;
-const RESET_KEY: usize = 12;
+const CONTROLLER_KEY: KeyCode = KeyCode::F2;
+const BALL_COUNT_KEY: KeyCode = KeyCode::F3;
+const BALL_KEY: KeyCode = KeyCode::F4;
+const ROUND_TIME_KEY: KeyCode = KeyCode::F5;
+const COURT_KEY: KeyCode = KeyCode::F6;
+/// The time, in seconds, between the end of a round and the next round, or the results screen
+const RESULTS_DELAY: f64 = 3.;
+/// Running the game with this argument turns off sound, for machines without an audio device
+const NO_AUDIO_ARG: &str = "--no-audio";
+/// Running the game with this argument checks the assets for problems, and exits, instead
+const VALIDATE_ASSETS_ARG: &str = "--validate-assets";
+/// The directory that resources are loaded from
+const ASSETS_DIR: &str = "resources";
+const INFIELDERS_PER_TEAM: usize = 3;
+const OUTFIELDERS_PER_TEAM: usize = 1;
 
 const TEAM_ONE_PLAYER: usize = 1;
 const TEAM_TWO_PLAYER: usize = 2;
@@ -51,6 +78,8 @@ pub enum PlayerAction {
     MoveRight,
     MoveUp,
     MoveDown,
+    /// Takes control of the next teammate
+    Switch,
 }
 
 enum MovingStates {
@@ -95,156 +124,213 @@ impl FacingTo {
 
 }
 
-fn calculate_life_color(life: i32) -> Color {
-    if life > 70 {
-        DARKGREEN
-    } else if life > 40 && life < 70 {
-        YELLOW
-    } else {
-        RED
-    }
-}
-
-fn valid_position(v: &Vec2) -> bool {
-    if v.x + PLAYER_HEIGHT > screen_width() {
-        return false;
-    }
-    if v.x < 0. {
-        return false;
-    }
-    if v.y + PLAYER_HEIGHT > screen_height() {
-        return false;
-    }
-    if v.y < 0. {
-        return false;
-    }
-    true
-}
 
-fn colliding_with(pos: &Vec2, r: f32, rect_pos: &Vec2, rect_size: &Vec2) -> (bool, bool, bool) {
-    // temporary variables to set edges for testing
-    let mut test_x = pos.x;
-    let mut test_y = pos.y;
-    // which edge is closest?
-    let change_x = if pos.x < rect_pos.x {
-        test_x = rect_pos.x;      // test left edge
-        true
-    } else if pos.x > rect_pos.x + rect_size.x {
-        test_x = rect_pos.x + rect_size.x;   // right edge
-        true
-    } else {
-        false
-    };
-    //
-    let change_y = if pos.y < rect_pos.y {
-        test_y = rect_pos.y;      // top edge
-        true
-    } else if pos.y > rect_pos.y + rect_size.y {
-        test_y = rect_pos.y + rect_size.y;   // bottom edge
-        true
-    } else {
-        false
+/// Plays a match with the given options, round after round, until a team has won enough rounds,
+/// or until the match is left from the pause menu
+async fn local_game(options: &mut MatchOptions) -> MatchEnd {
+    // team one is played with the key bindings of the active profile
+    let keys_mapped = {
+        let bindings = storage::get::<Profile>().key_bindings.clone();
+        vec![
+            bindings.key(&PlayerAction::MoveUp), bindings.key(&PlayerAction::MoveLeft),
+            bindings.key(&PlayerAction::MoveDown), bindings.key(&PlayerAction::MoveRight),
+            bindings.key(&PlayerAction::A), bindings.key(&PlayerAction::B),
+            bindings.key(&PlayerAction::Switch),
+            KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::Z, KeyCode::X, KeyCode::C,
+        ]
     };
-    // get distance from closest edges
-    let dist_x = pos.x - test_x;
-    let dist_y = pos.y - test_y;
-    let distance = ((dist_x * dist_x) + (dist_y * dist_y)).sqrt();
-    // if the distance is less than the radius, collision!
-    (distance <= r, change_x, change_y)
-}
-
-
-fn throw_ball(game: &mut Game, player_index: usize, team_with_ball: &Team) -> Option<usize> {
-    if !is_key_pressed(
-        *game.key_sets.get(&team_with_ball)
-            .unwrap().get(&PlayerAction::B)
-            .unwrap()
-    ) {
-        return None;
+    let mut match_state = MatchState::new(options);
+    let mut game = new_game(&keys_mapped, options).await;
+    // the time the round was won at, so the celebration can play out before the next round
+    let mut winner_time = None;
+    let mut pause_menu: Option<PauseMenu> = None;
+    let mut resource_watcher = ResourceWatcher::new();
+    // the game clock stops while the pause menu is open, so the time spent in it is left out
+    let mut paused_at: Option<f64> = None;
+    let mut paused_time = 0.;
+    loop {
+        if let Some(menu) = &mut pause_menu {
+            match menu.update() {
+                Some(PauseChoice::Resume) => pause_menu = None,
+                Some(PauseChoice::RestartRound) => {
+                    match_state.restart_round();
+                    game = new_game(&keys_mapped, options).await;
+                    winner_time = None;
+                    pause_menu = None;
+                }
+                Some(PauseChoice::RestartMatch) => {
+                    match_state = MatchState::new(options);
+                    game = new_game(&keys_mapped, options).await;
+                    winner_time = None;
+                    pause_menu = None;
+                }
+                Some(PauseChoice::Settings) => {
+                    // the Enter press that chose this is still down, so let it go before the
+                    // settings menu reads it as a toggle
+                    next_frame().await;
+                    settings_menu().await;
+                }
+                Some(PauseChoice::QuitToMenu) => {
+                    storage::get_mut::<Audio>().stop_music();
+                    return MatchEnd::QuitToMenu;
+                }
+                None => {}
+            }
+        } else if is_key_pressed(KeyCode::Escape) {
+            pause_menu = Some(PauseMenu::new());
+            paused_at = Some(get_time());
+        }
+        if pause_menu.is_none() {
+            if let Some(time) = paused_at.take() {
+                paused_time += get_time() - time;
+            }
+        }
+        game.time_passed = paused_at.unwrap_or_else(get_time) - paused_time;
+        if pause_menu.is_none() {
+            if let Some(winner) = game.winner {
+                let winner_time = *winner_time.get_or_insert(game.time_passed);
+                if game.time_passed - winner_time > RESULTS_DELAY {
+                    match_state.end_round(winner, &game.stats);
+                    if let Some(result) = match_state.result() {
+                        if options.team_one == Controller::Human {
+                            if let Err(err) = update_profile(|profile| profile.record_match(&result, Team::One)) {
+                                eprintln!("Could not save the profile: {}", err);
+                            }
+                        }
+                        storage::get_mut::<Audio>().stop_music();
+                        return MatchEnd::Finished(result);
+                    }
+                    game = new_game(&keys_mapped, options).await;
+                }
+            }
+            if game.winner.is_none() {
+                winner_time = None;
+            }
+            if is_key_pressed(CONTROLLER_KEY) {
+                options.team_two = options.team_two.next();
+            }
+            if is_key_pressed(BALL_COUNT_KEY) {
+                options.ball_count = options.next_ball_count();
+            }
+            if is_key_pressed(BALL_KEY) {
+                options.ball = options.next_ball(&storage::get::<Resources>().balls);
+            }
+            if is_key_pressed(ROUND_TIME_KEY) {
+                options.round_time = options.next_round_time();
+            }
+            if is_key_pressed(COURT_KEY) {
+                options.court = options.next_court(&storage::get::<Resources>().courts);
+            }
+            let options_changed = [CONTROLLER_KEY, BALL_COUNT_KEY, BALL_KEY, ROUND_TIME_KEY, COURT_KEY].into_iter().any(is_key_pressed);
+            if options_changed {
+                match_state = MatchState::new(options);
+                game = new_game(&keys_mapped, options).await;
+                winner_time = None;
+                game.set_zoom(None);
+                next_frame().await;
+                continue;
+            }
+            resource_watcher.update(&mut game).await;
+            let team_life = [game.team_life(Team::One), game.team_life(Team::Two)];
+            update_game(&mut game);
+            match_state.update(&mut game, team_life, get_frame_time());
+            game.dispatch_events();
+        }
+        draw_game(&mut game, options, &match_state);
+        if let Some(menu) = &pause_menu {
+            menu.draw();
+        }
+        resource_watcher.draw();
+        next_frame().await
     }
-    let other_team = other_team(*team_with_ball);
-    // find the opposite player
-    let target_player_index = game.get_active_player_for_team(other_team).unwrap();
-    let pos = game.ball.pos;
-    let target = game.players[target_player_index].pos;
-    let target_pos = (target - pos).normalize();
-    game.ball.throwing(target_pos, pos, game.players[player_index].facing_to_before.clone());
-    game.players[player_index].has_ball = false;
-    game.balls[game.ball.animation].set_animation(Ball::MOVE_ANIMATION_ID);
-    game.balls[game.ball.animation].update();
-    Some(target_player_index)
 }
 
-async fn local_game() {
-    // this should allow us to remap keys later
-    let keys_mapped = vec![
-        KeyCode::W, KeyCode::A, KeyCode::S, KeyCode::D, KeyCode::N, KeyCode::M,
-        KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::Z, KeyCode::X,
-        KeyCode::Enter,
-    ];
-    let mut game = new_game(&keys_mapped).await;
-    loop {
-        game.time_passed = get_time();
-        if is_key_pressed(keys_mapped[RESET_KEY]) {
-            game = new_game(&keys_mapped).await;
-            game.set_zoom(None);
-            next_frame().await;
-            continue;
+/// Steps the simulation of a round by a frame
+fn update_game(game: &mut Game) {
+    game.events.clear();
+    if game.hit_stop > 0 {
+        // freeze everything but the camera, so the shake still plays out
+        game.hit_stop -= 1;
+    } else {
+        for ball in &mut game.balls {
+            ball.prev_pos = ball.pos;
+        }
+        for i in 0..game.balls.len() {
+            game.update_ball_state(i);
         }
-        game.update_ball_state();
+        game.update_selected_players();
         // move player code outside, AI player will be separated
         for i in 0..game.players.len() {
             game.update_player(i);
         }
-        game.ball.move_ball();
-        game.is_the_ball_hitting_any_player();
-        game.is_ball_hitting_boundary();
-        debug_ball_throwing(&mut game);
-        //
-        //
-        // Drawing stuffs
-        //
-        //
-        match mouse_wheel() {
-            (_x, y) if y != 0.0 => {
-                let mut zoom = game.zoom;
-                zoom *= 1.1f32.powf(y);
-                game.set_zoom(Some([zoom.x, zoom.y]));
-            }
-            _ => (),
+        for ball in &mut game.balls {
+            ball.move_ball();
         }
-        clear_background(LIGHTGRAY);
-        draw_field(&game);
-        // set_camera(&Camera2D { target: game.ball.pos, rotation: 180., zoom: game.zoom, ..Default::default() });
-        debug_collision(&game);
-        game.balls[game.ball.animation].update();
-        let bx = _x(&game.ball);
-        let by = _y(&game.ball);
-        draw_rectangle_lines_a(game.ball.pos, game.ball.r, game.ball.r , 2., BLACK);
-        game.balls[game.ball.animation].draw(Vec2::new(bx, by), 0., false, false);
-
-        for (i, player) in game.players.iter().enumerate() {
-            let txt = format!("{}", player.life);
-            draw_text(&txt, player.pos.x, player.pos.y - 20., 20.0, calculate_life_color(player.life));
-            let flip_x = should_face_to(
-                player.facing_to.clone(),
-                if i >= game.players.len() { Team::Two } else { Team::Two },
-                player.facing_to_before.clone(),
-            );
-            // draw_rectangle_lines_a(player.pos, PLAYER_WIDTH, PLAYER_HEIGHT, 2., BLACK);
-            player.animation_player.draw(
-                player.pos, 0., flip_x, false,
-            );
+        game.is_ball_hitting_other_balls();
+        for i in 0..game.balls.len() {
+            game.is_the_ball_hitting_any_player(i);
+            game.is_ball_hitting_boundary(i);
+        }
+        game.check_for_winner();
+        debug_ball_throwing(game);
+        for animation in &mut game.ball_animations {
+            animation.update();
         }
-        next_frame().await
     }
+    game.update_camera();
 }
 
-fn check_for_collision(player: &mut Player, ball: &Ball) {
-    let (collided, change_x, change_y) = colliding_with(&ball.pos, ball.r,
-                                                        &player.pos,
-                                                        &Vec2::new(PLAYER_WIDTH, PLAYER_HEIGHT),
-    );
+fn draw_game(game: &mut Game, options: &MatchOptions, match_state: &MatchState) {
+    match mouse_wheel() {
+        (_x, y) if y != 0.0 => {
+            let mut zoom = game.zoom;
+            zoom *= 1.1f32.powf(y);
+            game.set_zoom(Some([zoom.x, zoom.y]));
+        }
+        _ => (),
+    }
+    clear_background(game.court.background_color);
+    set_camera(game.camera.get_camera());
+    draw_field(game);
+    debug_collision(game);
+    for ball in &game.balls {
+        let animation = &game.ball_animations[ball.animation];
+        let bx = _x(ball);
+        let by = _y(ball);
+        // `pos` is the center of the ball
+        draw_rectangle_lines_a(ball.pos - Vec2::new(ball.r, ball.r), ball.r * 2., ball.r * 2., 2., BLACK);
+        animation.draw(Vec2::new(bx, by) - animation.get_size() / 2., 0., false, false);
+    }
+
+    for (i, player) in game.players.iter().enumerate() {
+        // the marker shows which team the player is on
+        draw_circle(player.pos.x - 8., player.pos.y - 26., 6., player.color);
+        let flip_x = should_face_to(
+            player.facing_to.clone(),
+            if i >= game.players.len() { Team::Two } else { Team::Two },
+            player.facing_to_before.clone(),
+        );
+        // draw_rectangle_lines_a(player.pos, PLAYER_WIDTH, PLAYER_HEIGHT, 2., BLACK);
+        player.animation_player.draw(
+            player.pos, 0., flip_x, false,
+        );
+    }
+    game.particles.draw();
+    set_default_camera();
+    draw_hud(game, match_state);
+    let options_txt = [
+        format!("Team two: {} [F2]", options.team_two.label()),
+        format!("Balls: {} [F3]", options.ball_count),
+        format!("Ball: {} [F4]", options.ball),
+        match options.round_time {
+            0 => "Round time: none [F5]".to_string(),
+            time => format!("Round time: {}s [F5]", time),
+        },
+        format!("Court: {} [F6]", game.court.name),
+    ];
+    for (i, txt) in options_txt.iter().enumerate() {
+        draw_text(txt, 10., screen_height() - 90. + 20. * i as f32, 20., BLACK);
+    }
 }
 
 fn should_face_to(facing_to: FacingTo, which_team: Team, facing_to_before: FacingTo) -> bool {
@@ -267,23 +353,32 @@ fn should_face_to(facing_to: FacingTo, which_team: Team, facing_to_before: Facin
     }
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
-    load_resources("resources").await;
-    // player_animation_demo().await;
-    local_game().await;
-    //camera_test().await;
+fn main() {
+    // validating the assets is done without opening a window
+    if std::env::args().any(|arg| arg == VALIDATE_ASSETS_ARG) {
+        std::process::exit(validate_assets_command(ASSETS_DIR));
+    }
+    macroquad::Window::from_config(window_conf(), run());
+}
+
+async fn run() {
+    rand::srand(miniquad::date::now() as u64);
+    load_resources(ASSETS_DIR).await;
+    load_settings(ASSETS_DIR).await;
+    load_audio(!std::env::args().any(|arg| arg == NO_AUDIO_ARG)).await;
+    load_profile(ASSETS_DIR);
+    SceneManager::new().run().await;
 }
 
 
 fn debug_ball_throwing(game: &mut Game) {
     if is_mouse_button_pressed(MouseButton::Left) { // this is for testing purpose
-        let pos = Vec2::from(mouse_position());
-        // reposition the ball to cursor
-        game.ball.pos = pos;
-        game.gravity_line = game.ball.pos.y + PLAYER_HEIGHT;
+        let pos = game.camera.screen_to_world(Vec2::from(mouse_position()));
+        // reposition the first ball to cursor
+        game.balls[0].pos = pos;
+        game.balls[0].gravity_line = game.balls[0].pos.y + PLAYER_HEIGHT;
         // which team has the ball? and mark target player from opposite side
-        let m_team = game.which_team_has_ball();
+        let m_team = game.which_team_has_ball(0);
         let other_team = other_team(m_team);
         let target_player = game.get_active_player_for_team(other_team);
         let player: &Player = if target_player.is_some() {
@@ -292,36 +387,37 @@ fn debug_ball_throwing(game: &mut Game) {
             &game.players[0]
         };
         // fix target
-        let target_pos = (player.pos - game.ball.pos).normalize();
+        let target_pos = (player.pos - game.balls[0].pos).normalize();
         // throw it
-        game.ball.throwing(target_pos, pos, player.facing_to_before.clone())
+        let facing_to = player.facing_to_before.clone();
+        game.balls[0].throwing(target_pos, pos, facing_to)
     }
 
     if is_mouse_button_pressed(MouseButton::Right) { // this is for testing purpose
-        let pos = Vec2::from(mouse_position());
-        // reposition the ball to cursor
-        game.ball.pos = pos;
+        let pos = game.camera.screen_to_world(Vec2::from(mouse_position()));
+        // reposition the first ball to cursor
+        let ball = &mut game.balls[0];
+        ball.pos = pos;
         // which team has the ball? and mark target player from opposite side
         // fix target
-        let target_pos = (Vec2::new(game.ball.pos.x, game.ball.pos.y + 10.) - game.ball.pos).normalize();
-        game.gravity_line = game.ball.pos.y + PLAYER_HEIGHT;
+        let target_pos = (Vec2::new(ball.pos.x, ball.pos.y + 10.) - ball.pos).normalize();
+        ball.gravity_line = ball.pos.y + PLAYER_HEIGHT;
         // throw it
-        game.ball.vel = Vec2::new(-3., -1.);
-        game.ball.state = BallState::AfterHittingPlayer {
+        ball.vel = Vec2::new(-3., -1.);
+        ball.state = BallState::AfterHittingPlayer {
             time_passed:game.time_passed,
-            change_y: true,
-            change_x: true
+            normal: -ball.vel.normalize(),
         };
-        game.ball.throwing(target_pos, pos, FacingTo::FacingBottom)
+        ball.throwing(target_pos, pos, FacingTo::FacingBottom)
     }
 }
 
 fn debug_collision(game: &Game) {
-    let position = Vec2::from(mouse_position());
+    let position = game.camera.screen_to_world(Vec2::from(mouse_position()));
     for player in &game.players {
-        let (collided, _, _) = colliding_with(&position, 10., &player.pos,
-                                              &Vec2::new(PLAYER_WIDTH, PLAYER_HEIGHT));
-        if collided {
+        let collider = player.collider();
+        draw_rectangle_lines(collider.x, collider.y, collider.w, collider.h, 1., DARKGRAY);
+        if circle_rect_contact(position, 10., collider).is_some() {
             draw_rectangle_lines_a(position, 20., 20., 3., RED);
         }
     }
@@ -332,11 +428,19 @@ fn debug_collision(game: &Game) {
 }
 
 fn draw_field(game: &Game) {
-    draw_line_a(game.field.top_left, game.field.top_right, 3., BLACK);
-    draw_line_a(game.field.top_left, game.field.bottom_left, 3., DARKGREEN);
-    draw_line_a(game.field.bottom_left, game.field.bottom_right, 3., RED);
-    draw_line_a(game.field.top_right, game.field.bottom_right, 3., GREEN);
-    draw_line_a(game.field.mid_section_top, game.field.mid_section_bottom, 3., YELLOW);
+    let field = &game.field;
+    let size = field.bottom_right - field.top_left;
+    draw_rectangle(field.top_left.x, field.top_left.y, size.x, size.y, game.court.floor_color);
+    let line_color = game.court.line_color;
+    draw_line_a(field.top_left, field.top_right, 3., line_color);
+    draw_line_a(field.top_left, field.bottom_left, 3., line_color);
+    draw_line_a(field.bottom_left, field.bottom_right, 3., line_color);
+    draw_line_a(field.top_right, field.bottom_right, 3., line_color);
+    draw_line_a(field.mid_section_top, field.mid_section_bottom, 3., line_color);
+    for obstacle in &game.court.obstacles {
+        let rect = obstacle.rect(field);
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, obstacle.color);
+    }
 }
 
 async fn player_animation_demo() {
@@ -385,16 +489,19 @@ async fn player_animation_demo() {
             animation_player.draw(position, 0.0, false, false);
             position += offset;
         }
-        if is_key_pressed(KeyCode::Enter) {
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Escape) {
             break;
         }
         next_frame().await;
     }
 }
 
-async fn new_game(keys_mapped: &Vec<KeyCode>) -> Game {
+async fn new_game(keys_mapped: &Vec<KeyCode>, options: &MatchOptions) -> Game {
     let resources = storage::get::<Resources>();
     let mut game = Game::default();
+    if let Some(court) = resources.courts.iter().find(|court| court.id == options.court) {
+        game.court = court.clone();
+    }
     game.key_sets = HashMap::from([(
         Team::One, HashMap::from([
             (PlayerAction::MoveUp, keys_mapped[0]),
@@ -403,73 +510,126 @@ async fn new_game(keys_mapped: &Vec<KeyCode>) -> Game {
             (PlayerAction::MoveRight, keys_mapped[3]),
             (PlayerAction::A, keys_mapped[4]),
             (PlayerAction::B, keys_mapped[5]),
+            (PlayerAction::Switch, keys_mapped[6]),
         ])),
         (Team::Two, HashMap::from([
-            (PlayerAction::MoveUp, keys_mapped[6]),
-            (PlayerAction::MoveLeft, keys_mapped[7]),
-            (PlayerAction::MoveDown, keys_mapped[8]),
-            (PlayerAction::MoveRight, keys_mapped[9]),
-            (PlayerAction::A, keys_mapped[10]),
-            (PlayerAction::B, keys_mapped[11]),
+            (PlayerAction::MoveUp, keys_mapped[7]),
+            (PlayerAction::MoveLeft, keys_mapped[8]),
+            (PlayerAction::MoveDown, keys_mapped[9]),
+            (PlayerAction::MoveRight, keys_mapped[10]),
+            (PlayerAction::A, keys_mapped[11]),
+            (PlayerAction::B, keys_mapped[12]),
+            (PlayerAction::Switch, keys_mapped[13]),
         ]))
     ]);
-    game.players = { // Player animations
-        let player_animations = || {
-            let mut animation_params: AnimationParams = resources.player_characters[0].animation.clone().into();
-            let mut animation_player = AnimationPlayer::new(animation_params);
-            animation_player.set_animation(Player::IDLE_ANIMATION_ID);
-            animation_player.set_scale(1.);
-            animation_player
-        };
+    game.players = {
         let mut players = vec![];
-        let p1_pos = Vec2::new(game.field.mid_section - PLAYER_WIDTH - 80., screen_height() / 2.);
-        let p2_pos = Vec2::new(game.field.mid_section + 80., screen_height() / 2.);
-        players.push(Player::new(
-            0,
-            p1_pos,
-            90.,
-            Vec2::new(0., 0.),
-            100,
-            false,
-            BLACK,
-            FacingTo::FacingRight,
-            player_animations(),
-        ));
-
-        players.push(Player::new(
-            1,
-            p2_pos,
-            -90.,
-            Vec2::new(0., 0.),
-            100,
-            false,
-            DARKGRAY,
-            FacingTo::FacingLeft,
-            player_animations(),
-        ));
+        for team in [Team::One, Team::Two] {
+            let team_params = options.team_id(team)
+                .and_then(|id| resources.teams.iter().find(|params| params.id == id));
+            if let Some(team_params) = team_params {
+                game.teams.insert(team, team_params.clone());
+            }
+            let facing_to = match team {
+                Team::One => FacingTo::FacingRight,
+                Team::Two => FacingTo::FacingLeft,
+            };
+            let color = game.team_color(team);
+            for slot in 0..INFIELDERS_PER_TEAM + OUTFIELDERS_PER_TEAM {
+                let slot_params = team_params.and_then(|params| params.roster.get(slot));
+                // slots without a pick get the team's character, or the first character
+                let character = options.character(team, slot)
+                    .or_else(|| slot_params.map(|slot_params| slot_params.character.as_str()))
+                    .and_then(|id| resources.player_characters.iter().find(|character| character.id == id))
+                    .unwrap_or(&resources.player_characters[0]);
+                let is_outfielder = slot >= INFIELDERS_PER_TEAM;
+                let zone = game.field.player_zone(team, is_outfielder);
+                let lanes = (INFIELDERS_PER_TEAM + 1) as f32;
+                let (x, y, facing_to) = if is_outfielder {
+                    // outfielders face their own team's opponents, from behind
+                    let (facing_to, _) = FacingTo::opposite_direction(facing_to);
+                    (zone.x + zone.w / 2., zone.y + zone.h / 2., facing_to)
+                } else {
+                    // infielders start spread out over their half, staggered towards the mid line
+                    let y = zone.y + zone.h * (slot + 1) as f32 / lanes;
+                    let distance_from_mid = 80. + 100. * (slot % 2) as f32;
+                    let x = match team {
+                        Team::One => zone.x + zone.w - distance_from_mid,
+                        Team::Two => zone.x + distance_from_mid,
+                    };
+                    (x, y, facing_to)
+                };
+                let mut player = Player::new(
+                    players.len() as u8,
+                    Vec2::new(x, y),
+                    if facing_to == FacingTo::FacingRight { 90. } else { -90. },
+                    Vec2::new(0., 0.),
+                    slot_params.map_or(RosterSlotParams::default_life(), |slot_params| slot_params.life),
+                    false,
+                    color,
+                    facing_to,
+                    character.animation_player(),
+                );
+                player.is_outfielder = is_outfielder;
+                player.set_character(character);
+                if let Some(slot_params) = slot_params {
+                    player.power = slot_params.power;
+                    player.move_speed *= slot_params.speed;
+                }
+                player.animation_player.set_tint(kit_tint(color));
+                players.push(player);
+            }
+        }
         players
     };
-    game.balls = {
-        let mut animation_params: AnimationParams = resources.balls[0].animation.clone().into();
-        let mut animation_player = AnimationPlayer::new(animation_params);
-        animation_player.set_animation(Ball::IDLE_ANIMATION_ID);
-        vec![animation_player]
-    };
-    game.ball = Ball {
-        pos: game.players[0].pos,
-        vel: Vec2::default(),
-        r: 16.,
-        collided: false,
-        thrown: false,
-        dropped: false,
-        color: BLACK,
-        in_air: false,
-        grabbed_by: Some(0),
-        animation: 0,
-        stopped: true,
-        state: BallState::OnPlayersHand(0),
-        tick: 0.0
-    };
+    for team in [Team::One, Team::Two] {
+        if let Controller::Cpu(difficulty) = options.controller(team) {
+            game.ai.insert(team, AiController::new(difficulty));
+        }
+    }
+    let ball_params = resources.balls.iter()
+        .find(|params| params.id == options.ball)
+        .unwrap_or(&resources.balls[0]);
+    game.ball_id = ball_params.id.clone();
+    for ball_index in 0..options.ball_count {
+        game.ball_animations.push(ball_params.animation_player());
+        // the first ball starts in the hands of team one, the others are spread over the mid line
+        let (pos, grabbed_by, state) = if ball_index == 0 {
+            (game.players[0].pos, Some(0), BallState::OnPlayersHand(0))
+        } else {
+            let field = &game.field;
+            let y = field.top_edge + (field.bottom_edge - field.top_edge) * ball_index as f32 / options.ball_count as f32;
+            (Vec2::new(field.mid_section, y), None, BallState::OnGround)
+        };
+        game.balls.push(Ball {
+            pos,
+            prev_pos: pos,
+            vel: Vec2::default(),
+            r: ball_params.physics.radius,
+            collided: false,
+            thrown: false,
+            dropped: false,
+            color: BLACK,
+            in_air: false,
+            grabbed_by,
+            thrown_by: None,
+            animation: ball_index,
+            stopped: true,
+            state,
+            tick: 0.0,
+            super_shot: false,
+            gravity_line: pos.y + PLAYER_HEIGHT,
+            physics: ball_params.physics,
+            dodged_by: Vec::new(),
+        });
+    }
+    game.attach_ball_to_player(0, 0);
+    game.particles = Particles::new(&resources.particle_effects);
+    game.build_world();
+    game.reset_stats();
+    if let Some(mut audio) = storage::try_get_mut::<Audio>() {
+        audio.start_round(&game.court.music);
+    }
     game
 }
 
@@ -165,48 +165,131 @@ pub fn color_from_hex_string(str: &str) -> Color {
     )
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Where a circle touches a shape, as found by `circle_rect_contact`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Contact {
+    /// The direction, from the shape to the circle, that the circle should be pushed out in
+    pub normal: Vec2,
+    /// How deep the circle is into the shape
+    pub penetration: f32,
+}
 
-    #[test]
-    fn test_color_from_hex_string_no_hash() {
-        assert_eq!(
-            color_from_hex_string("12ab6f"),
-            Color::new(
-                18 as f32 / 255.0,
-                171 as f32 / 255.0,
-                111 as f32 / 255.0,
-                255 as f32 / 255.0,
-            )
-        );
+/// Returns the contact between a circle and a `Rect`, if they touch. If the center of the circle is
+/// inside of the `Rect`, it is pushed out through the closest edge
+pub fn circle_rect_contact(center: Vec2, r: f32, rect: Rect) -> Option<Contact> {
+    let closest = vec2(
+        center.x.clamp(rect.left(), rect.right()),
+        center.y.clamp(rect.top(), rect.bottom()),
+    );
+    let delta = center - closest;
+    let distance = delta.length();
+    if distance > r {
+        return None;
     }
+    if distance > 0. {
+        return Some(Contact {
+            normal: delta / distance,
+            penetration: r - distance,
+        });
+    }
+    let edges = [
+        (center.x - rect.left(), vec2(-1., 0.)),
+        (rect.right() - center.x, vec2(1., 0.)),
+        (center.y - rect.top(), vec2(0., -1.)),
+        (rect.bottom() - center.y, vec2(0., 1.)),
+    ];
+    let (depth, normal) = edges
+        .into_iter()
+        .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap())
+        .unwrap();
+    Some(Contact {
+        normal,
+        penetration: depth + r,
+    })
+}
 
-    #[test]
-    fn test_color_from_hex_string_hash() {
-        assert_eq!(
-            color_from_hex_string("#12ab6f"),
-            Color::new(
-                18 as f32 / 255.0,
-                171 as f32 / 255.0,
-                111 as f32 / 255.0,
-                255 as f32 / 255.0,
-            )
-        );
+/// Where a moving circle first touches a shape, as found by `sweep_circle_rect`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SweepHit {
+    /// The fraction of the movement, from 0 to 1, at which the circle touches the shape
+    pub time: f32,
+    /// The direction, from the shape to the circle, at the time of the contact
+    pub normal: Vec2,
+}
+
+/// Sweeps a circle from `start` to `end`, and returns where it first touches a `Rect`, so that a
+/// fast circle can not pass through a `Rect` between two frames. If the circle touches the `Rect`
+/// at `start` already, the contact is at time 0, with the normal of `circle_rect_contact`
+pub fn sweep_circle_rect(start: Vec2, end: Vec2, r: f32, rect: Rect) -> Option<SweepHit> {
+    if let Some(contact) = circle_rect_contact(start, r, rect) {
+        return Some(SweepHit { time: 0., normal: contact.normal });
+    }
+    let delta = end - start;
+    // the center of the circle hits the rect grown by the radius, with rounded corners. This
+    // finds when it enters the grown rect, with square corners, first
+    let mut entry = 0f32;
+    let mut exit = 1f32;
+    let mut normal = Vec2::ZERO;
+    for (start, delta, min, max, axis) in [
+        (start.x, delta.x, rect.left() - r, rect.right() + r, vec2(1., 0.)),
+        (start.y, delta.y, rect.top() - r, rect.bottom() + r, vec2(0., 1.)),
+    ] {
+        if delta == 0. {
+            if start < min || start > max {
+                return None;
+            }
+            continue;
+        }
+        let (near, far, side) = if delta > 0. {
+            ((min - start) / delta, (max - start) / delta, -axis)
+        } else {
+            ((max - start) / delta, (min - start) / delta, axis)
+        };
+        if near > entry {
+            entry = near;
+            normal = side;
+        }
+        exit = exit.min(far);
+        if entry > exit {
+            return None;
+        }
+    }
+    let point = start + delta * entry;
+    let is_on_side = (rect.left()..=rect.right()).contains(&point.x)
+        || (rect.top()..=rect.bottom()).contains(&point.y);
+    if is_on_side {
+        return Some(SweepHit { time: entry, normal });
     }
+    // the entry point is in one of the corners, which are rounded, so the circle is swept against
+    // the corner itself
+    let corner = vec2(
+        if point.x < rect.left() { rect.left() } else { rect.right() },
+        if point.y < rect.top() { rect.top() } else { rect.bottom() },
+    );
+    let to_start = start - corner;
+    let a = delta.dot(delta);
+    let b = 2. * to_start.dot(delta);
+    let c = to_start.dot(to_start) - r * r;
+    let discriminant = b * b - 4. * a * c;
+    if discriminant < 0. {
+        return None;
+    }
+    let time = (-b - discriminant.sqrt()) / (2. * a);
+    if !(0. ..=1.).contains(&time) {
+        return None;
+    }
+    let normal = (start + delta * time - corner) / r;
+    Some(SweepHit { time, normal })
+}
 
-    #[test]
-    fn test_color_from_hex_string_alpha() {
-        assert_eq!(
-            color_from_hex_string("12ab6fb2"),
-            Color::new(
-                18 as f32 / 255.0,
-                171 as f32 / 255.0,
-                111 as f32 / 255.0,
-                178 as f32 / 255.0,
-            )
-        );
+/// Reflects a velocity off a surface with the given normal, keeping `bounciness` of the speed
+/// along the normal. Velocities that are already moving away from the surface are left alone
+pub fn reflect(velocity: Vec2, normal: Vec2, bounciness: f32) -> Vec2 {
+    let speed_along_normal = velocity.dot(normal);
+    if speed_along_normal >= 0. {
+        return velocity;
     }
+    velocity - normal * speed_along_normal * (1. + bounciness)
 }
 
 pub fn rotate_vector(vec: Vec2, rad: f32) -> Vec2 {
@@ -245,3 +328,92 @@ impl IsZero for Vec2 {
         *self == Vec2::ZERO
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circle_rect_contact_outside() {
+        let rect = Rect::new(0., 0., 10., 20.);
+        assert_eq!(circle_rect_contact(vec2(15., 10.), 4., rect), None);
+        assert_eq!(
+            circle_rect_contact(vec2(12., 10.), 4., rect),
+            Some(Contact { normal: vec2(1., 0.), penetration: 2. })
+        );
+    }
+
+    #[test]
+    fn test_circle_rect_contact_inside() {
+        let rect = Rect::new(0., 0., 10., 20.);
+        assert_eq!(
+            circle_rect_contact(vec2(5., 2.), 4., rect),
+            Some(Contact { normal: vec2(0., -1.), penetration: 6. })
+        );
+    }
+
+    #[test]
+    fn test_sweep_circle_rect_tunneling() {
+        // the circle jumps over the whole rect in a single step
+        let rect = Rect::new(0., 0., 4., 20.);
+        let hit = sweep_circle_rect(vec2(-10., 10.), vec2(30., 10.), 2., rect).unwrap();
+        assert_eq!(hit, SweepHit { time: 0.2, normal: vec2(-1., 0.) });
+        assert_eq!(sweep_circle_rect(vec2(-10., 30.), vec2(30., 30.), 2., rect), None);
+    }
+
+    #[test]
+    fn test_sweep_circle_rect_corner() {
+        let rect = Rect::new(0., 0., 10., 10.);
+        // passes the corner without touching it, though it enters the grown rect
+        assert_eq!(sweep_circle_rect(vec2(-6., 7.), vec2(0., 13.), 2., rect), None);
+        let hit = sweep_circle_rect(vec2(-5., -5.), vec2(5., 5.), 2., rect).unwrap();
+        let expected = std::f32::consts::FRAC_1_SQRT_2;
+        assert!((hit.normal - vec2(-expected, -expected)).length() < 1e-4);
+    }
+
+    #[test]
+    fn test_reflect() {
+        let velocity = reflect(vec2(3., 4.), vec2(-1., 0.), 0.5);
+        assert_eq!(velocity, vec2(-1.5, 4.));
+        assert_eq!(reflect(vec2(3., 4.), vec2(1., 0.), 0.5), vec2(3., 4.));
+    }
+
+    #[test]
+    fn test_color_from_hex_string_no_hash() {
+        assert_eq!(
+            color_from_hex_string("12ab6f"),
+            Color::new(
+                18 as f32 / 255.0,
+                171 as f32 / 255.0,
+                111 as f32 / 255.0,
+                255 as f32 / 255.0,
+            )
+        );
+    }
+
+    #[test]
+    fn test_color_from_hex_string_hash() {
+        assert_eq!(
+            color_from_hex_string("#12ab6f"),
+            Color::new(
+                18 as f32 / 255.0,
+                171 as f32 / 255.0,
+                111 as f32 / 255.0,
+                255 as f32 / 255.0,
+            )
+        );
+    }
+
+    #[test]
+    fn test_color_from_hex_string_alpha() {
+        assert_eq!(
+            color_from_hex_string("12ab6fb2"),
+            Color::new(
+                18 as f32 / 255.0,
+                171 as f32 / 255.0,
+                111 as f32 / 255.0,
+                178 as f32 / 255.0,
+            )
+        );
+    }
+}